@@ -8,14 +8,29 @@
 use image::RgbaImage;
 use thiserror::Error;
 use ultraviolet::{Mat4, Vec3};
+use vulkano::swapchain::PresentMode;
 use winit::event::{Event, StartCause, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::Window;
 
 use crate::{
-    config::Config,
-    graphics::{camera::CameraUBO, error::ImageRegisterError, Renderer, RendererCreationError},
-    window::{Event as MyEvent, Size},
+    config::{Config, DebugSeverity},
+    graphics::{
+        camera::CameraUBO,
+        error::{
+            DeviceUuidError, DriverPropertiesError, HdrMetadataError, ImageRegisterError,
+            PresentModeError, SubgroupPropertiesError,
+        },
+        BlurBehind, BlurBehindCreationError, ConservativeRasterProperties, DeviceReport,
+        DriverProperties, HdrMetadata, PciBusInfo, Renderer, RendererCreationError,
+        SparseProperties, SubgroupProperties, UiCapture, UiCaptureCreationError, UiSupersampler,
+        UiSupersamplerCreationError,
+    },
+    window::{Event as MyEvent, PhysicalSize},
+};
+
+pub use crate::graphics::{
+    MsdfOptions, TextureFilter, TextureOptions, TextureWrapMode, UiDrawSystem, YAxis,
 };
 
 pub type Result<T> = std::result::Result<T, AppCreationError>;
@@ -70,11 +85,112 @@ pub fn window(&self) -> &Window {
         self.renderer.window()
     }
 
+    /// Returns system that renders UI of this application.
+    pub fn ui_draw_system(&mut self) -> &mut UiDrawSystem {
+        self.renderer.ui_draw_system()
+    }
+
+    /// Sets present mode of the window (e.g. to toggle vertical synchronization).
+    ///
+    /// Returns if the swapchain had to be recreated.
+    ///
+    pub fn set_present_mode(
+        &mut self,
+        present_mode: PresentMode,
+    ) -> std::result::Result<bool, PresentModeError> {
+        self.renderer.set_present_mode(present_mode)
+    }
+
+    /// Sets timeout of acquiring next image of the window; `None` means infinite timeout.
+    ///
+    /// Frame is skipped if no image became available in time.
+    ///
+    pub fn set_acquire_timeout(&mut self, timeout: Option<Duration>) {
+        self.renderer.set_acquire_timeout(timeout)
+    }
+
+    /// Sets minimal severity of messages which are reported by debug messenger, if installed.
+    pub fn set_debug_severity(&self, min_severity: DebugSeverity) {
+        self.renderer.set_debug_severity(min_severity)
+    }
+
+    /// Collects information about the physical device (useful for bug reports).
+    pub fn device_report(&self) -> DeviceReport {
+        self.renderer.device_report()
+    }
+
+    /// Creates helper that blurs regions of images behind UI panels.
+    pub fn create_blur_behind(&self) -> std::result::Result<BlurBehind, BlurBehindCreationError> {
+        self.renderer.create_blur_behind()
+    }
+
+    /// Creates system that renders UI into an offscreen image (e.g. for screenshots).
+    pub fn create_ui_capture(&self) -> std::result::Result<UiCapture, UiCaptureCreationError> {
+        self.renderer.create_ui_capture()
+    }
+
+    /// Creates system that renders UI at higher internal resolution for sharper small fonts.
+    pub fn create_ui_supersampler(
+        &self,
+        factor: f32,
+    ) -> std::result::Result<UiSupersampler, UiSupersamplerCreationError> {
+        self.renderer.create_ui_supersampler(factor)
+    }
+
+    /// Returns subgroup properties of the physical device used by this application.
+    pub fn subgroup_properties(
+        &self,
+    ) -> std::result::Result<SubgroupProperties, SubgroupPropertiesError> {
+        self.renderer.subgroup_properties()
+    }
+
+    /// Returns count of nanoseconds per tick of GPU timestamps, if timestamps are supported.
+    pub fn timestamp_period(&self) -> Option<f32> {
+        self.renderer.timestamp_period()
+    }
+
+    /// Returns conservative rasterization properties of the physical device
+    /// used by this application, if supported.
+    pub fn conservative_raster_properties(&self) -> Option<ConservativeRasterProperties> {
+        self.renderer.conservative_raster_properties()
+    }
+
+    /// Returns UUID of the physical device used by this application.
+    pub fn device_uuid(&self) -> std::result::Result<[u8; 16], DeviceUuidError> {
+        self.renderer.device_uuid()
+    }
+
+    /// Returns location on PCI bus of the physical device used by this application, if supported.
+    pub fn pci_bus_info(&self) -> Option<PciBusInfo> {
+        self.renderer.pci_bus_info()
+    }
+
+    /// Returns sparse resource support of the physical device used by this application.
+    pub fn sparse_properties(&self) -> SparseProperties {
+        self.renderer.sparse_properties()
+    }
+
+    /// Sets mastering metadata of HDR content presented by this application.
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: HdrMetadata,
+    ) -> std::result::Result<(), HdrMetadataError> {
+        self.renderer.set_hdr_metadata(metadata)
+    }
+
+    /// Returns driver properties of the physical device used by this application.
+    pub fn driver_properties(
+        &self,
+    ) -> std::result::Result<DriverProperties, DriverPropertiesError> {
+        self.renderer.driver_properties()
+    }
+
     pub fn register_ui_image(
         &mut self,
         image: &RgbaImage,
+        options: TextureOptions,
     ) -> std::result::Result<TextureId, ImageRegisterError> {
-        self.renderer.register_ui_image(image)
+        self.renderer.register_ui_image(image, options)
     }
 
     /// Starts execution of game engine.
@@ -111,7 +227,7 @@ pub fn run(mut self, mut callback: impl FnMut(MyEvent) + 'static) -> ! {
                             WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
                             WindowEvent::Resized(size) => {
                                 if size.width == 0 || size.height == 0 {
-                                    callback(MyEvent::Resized(Size::default()));
+                                    callback(MyEvent::Resized(PhysicalSize::default()));
                                     return;
                                 }
                                 if let Err(error) = self.renderer.resize() {
@@ -119,13 +235,12 @@ pub fn run(mut self, mut callback: impl FnMut(MyEvent) + 'static) -> ! {
                                     *control_flow = ControlFlow::Exit;
                                     return;
                                 }
-                                let size = (size.width, size.height);
                                 callback(MyEvent::Resized(size.into()));
                             }
                             WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                                let size = *new_inner_size;
+                                let size = PhysicalSize::from(*new_inner_size);
                                 if size.width == 0 || size.height == 0 {
-                                    callback(MyEvent::Resized(Size::default()));
+                                    callback(MyEvent::Resized(PhysicalSize::default()));
                                     return;
                                 }
                                 if let Err(error) = self.renderer.resize() {
@@ -133,8 +248,7 @@ pub fn run(mut self, mut callback: impl FnMut(MyEvent) + 'static) -> ! {
                                     *control_flow = ControlFlow::Exit;
                                     return;
                                 }
-                                let size = (size.width, size.height);
-                                callback(MyEvent::Resized(size.into()));
+                                callback(MyEvent::Resized(size));
                             }
                             _ => (),
                         }
@@ -175,7 +289,7 @@ pub fn run(mut self, mut callback: impl FnMut(MyEvent) + 'static) -> ! {
                             use ultraviolet::projection::perspective_vk as perspective;
                             let projection = perspective(
                                 45f32.to_radians(),
-                                (size.width as f32) / (size.height as f32),
+                                PhysicalSize::from(size).aspect_ratio(),
                                 1.0,
                                 10.0,
                             );
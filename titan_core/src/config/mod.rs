@@ -1,13 +1,49 @@
 //! Configuration utilities for game engine and your game.
 
 use semver::Version;
+use vulkano::device::physical::PhysicalDeviceType;
+
+mod tests;
 
 /// This struct represents general configuration of game engine.
 #[derive(Debug, Clone)]
 pub struct Config {
     name: String,
     version: Version,
-    enable_validation: bool,
+    enable_validation: Option<bool>,
+    preferred_device: Option<DeviceSelector>,
+    device_features: DeviceFeatureChain,
+    validation_features: ValidationFeatures,
+    max_api_version: Option<vulkano::Version>,
+    debug_messenger: DebugMessengerSetting,
+    queue_priorities: QueuePriorities,
+    engine_name: Option<String>,
+    engine_version: Option<Version>,
+    instance_retry: bool,
+}
+
+/// Selector of physical device which should be used by game engine.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceSelector {
+    /// Physical device with given index in enumeration order.
+    Index(usize),
+
+    /// Physical device which name contains given substring.
+    Name(String),
+
+    /// Physical device of given type.
+    Type(PhysicalDeviceType),
+}
+
+impl DeviceSelector {
+    /// Checks if physical device with given index, name and type is matched by this selector.
+    pub fn matches(&self, index: usize, name: &str, ty: PhysicalDeviceType) -> bool {
+        match self {
+            Self::Index(selected) => *selected == index,
+            Self::Name(substring) => name.contains(substring.as_str()),
+            Self::Type(selected) => *selected == ty,
+        }
+    }
 }
 
 pub const ENGINE_NAME: &str = env!("CARGO_CRATE_NAME", "library must be compiled by Cargo");
@@ -17,16 +53,281 @@ pub struct Config {
     pub static ref ENGINE_VERSION: Version = ENGINE_VERSION_STR.parse().unwrap();
 }
 
+/// Environment variable which enables (`1`) or disables (`0`) validation
+/// if it is not set explicitly by the configuration.
+pub const VALIDATION_VAR: &str = "TITAN_VALIDATION";
+
+/// Highest version of Vulkan API which is supported by game engine.
+pub const MAX_API_VERSION: vulkano::Version = vulkano::Version::V1_2;
+
+/// Optional features of the device which should be enabled by game engine.
+///
+/// Support of each requested feature is verified before the device creation.
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DeviceFeatureChain {
+    timeline_semaphore: bool,
+    descriptor_indexing: bool,
+}
+
+impl DeviceFeatureChain {
+    /// Creates new feature chain with no features requested.
+    pub const fn new() -> Self {
+        Self {
+            timeline_semaphore: false,
+            descriptor_indexing: false,
+        }
+    }
+
+    /// Requests timeline semaphores to be enabled.
+    pub const fn with_timeline_semaphore(self) -> Self {
+        Self {
+            timeline_semaphore: true,
+            ..self
+        }
+    }
+
+    /// Requests descriptor indexing features to be enabled
+    /// (runtime descriptor arrays, dynamic and non-uniform indexing of sampled images,
+    /// partially bound and variable-count descriptor bindings).
+    ///
+    /// Requires device which supports Vulkan 1.2.
+    ///
+    pub const fn with_descriptor_indexing(self) -> Self {
+        Self {
+            descriptor_indexing: true,
+            ..self
+        }
+    }
+
+    /// If timeline semaphores were requested.
+    pub const fn timeline_semaphore(&self) -> bool {
+        self.timeline_semaphore
+    }
+
+    /// If descriptor indexing features were requested.
+    pub const fn descriptor_indexing(&self) -> bool {
+        self.descriptor_indexing
+    }
+}
+
+/// Optional features of validation which are enabled in addition to the basic validation.
+///
+/// Used only if validation is enabled. Features are passed to Khronos validation layer
+/// by `VK_LAYER_ENABLES` environment variable, which must be set before the application starts;
+/// if it is not set, its value for requested features is logged.
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ValidationFeatures {
+    /// GPU-assisted validation of shaders.
+    pub gpu_assisted: bool,
+
+    /// Warnings about potential API misuse and performance issues.
+    pub best_practices: bool,
+
+    /// Validation of resource access synchronization.
+    pub synchronization: bool,
+}
+
+/// Minimal severity of messages which are reported by debug messenger.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DebugSeverity {
+    /// Diagnostic messages of the loader, layers and drivers.
+    Verbose,
+
+    /// Informational messages (e.g. about resource creation).
+    Info,
+
+    /// Messages about potential API misuse.
+    Warning,
+
+    /// Messages about invalid API usage.
+    Error,
+}
+
+/// Setting of debug messenger which reports messages of Vulkan into the global logger.
+///
+/// Debug messenger is independent of validation: validation layer could be enabled
+/// without the messenger (then it reports messages by its own means),
+/// and the messenger could be installed without validation.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DebugMessengerSetting {
+    /// Debug messenger is not installed.
+    Disabled,
+
+    /// Debug messenger is installed and reports messages of given or higher severity.
+    Enabled { min_severity: DebugSeverity },
+}
+
+impl DebugMessengerSetting {
+    /// Default setting: messenger reports warnings and errors in debug builds
+    /// and is disabled in release builds.
+    pub const fn new() -> Self {
+        if cfg!(debug_assertions) {
+            Self::Enabled {
+                min_severity: DebugSeverity::Warning,
+            }
+        } else {
+            Self::Disabled
+        }
+    }
+
+    /// If debug messenger should be installed.
+    pub const fn is_enabled(&self) -> bool {
+        matches!(self, Self::Enabled { .. })
+    }
+}
+
+impl Default for DebugMessengerSetting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Priorities of queues which are created by game engine.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct QueuePriorities {
+    /// Priority of the graphics queue in range `0.0..=1.0`.
+    pub graphics: f32,
+
+    /// Priority of the async compute queue in range `0.0..=1.0`.
+    ///
+    /// If set, async compute queue is created on the queue family
+    /// which supports compute but not graphics operations (if there is any).
+    ///
+    pub async_compute: Option<f32>,
+}
+
+impl QueuePriorities {
+    /// Creates new queue priorities with the highest graphics priority and no async compute.
+    pub const fn new() -> Self {
+        Self {
+            graphics: 1.0,
+            async_compute: None,
+        }
+    }
+}
+
+impl Default for QueuePriorities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Config {
     /// Creates new configuration with given name, version and validation usage.
     pub const fn new(name: String, version: Version, enable_validation: bool) -> Self {
+        Self::with_optional_validation(name, version, Some(enable_validation))
+    }
+
+    /// Creates new configuration with given name and version
+    /// which does not set validation usage explicitly.
+    ///
+    /// See [`enable_validation`](Config::enable_validation) for how validation usage is chosen.
+    ///
+    pub const fn with_default_validation(name: String, version: Version) -> Self {
+        Self::with_optional_validation(name, version, None)
+    }
+
+    const fn with_optional_validation(
+        name: String,
+        version: Version,
+        enable_validation: Option<bool>,
+    ) -> Self {
         Self {
             name,
             version,
             enable_validation,
+            preferred_device: None,
+            device_features: DeviceFeatureChain::new(),
+            validation_features: ValidationFeatures {
+                gpu_assisted: false,
+                best_practices: false,
+                synchronization: false,
+            },
+            max_api_version: None,
+            debug_messenger: DebugMessengerSetting::new(),
+            queue_priorities: QueuePriorities::new(),
+            engine_name: None,
+            engine_version: None,
+            instance_retry: false,
         }
     }
 
+    /// Sets physical device which should be preferred by game engine.
+    ///
+    /// If no physical device matches the selector, game engine will fall back
+    /// to its own choice of the most suitable physical device.
+    ///
+    pub fn with_preferred_device(mut self, selector: DeviceSelector) -> Self {
+        self.preferred_device = Some(selector);
+        self
+    }
+
+    /// Sets optional device features which should be enabled by game engine.
+    pub fn with_device_features(mut self, device_features: DeviceFeatureChain) -> Self {
+        self.device_features = device_features;
+        self
+    }
+
+    /// Sets optional features of validation which should be enabled by game engine.
+    pub fn with_validation_features(mut self, validation_features: ValidationFeatures) -> Self {
+        self.validation_features = validation_features;
+        self
+    }
+
+    /// Sets highest version of Vulkan API which could be used by game engine.
+    ///
+    /// Useful to reproduce bugs on lower API versions.
+    /// Version actually used never exceeds one supported by the driver.
+    ///
+    pub fn with_max_api_version(mut self, max_api_version: vulkano::Version) -> Self {
+        self.max_api_version = Some(max_api_version);
+        self
+    }
+
+    /// Sets if and how debug messenger should be installed by game engine.
+    pub fn with_debug_messenger(mut self, debug_messenger: DebugMessengerSetting) -> Self {
+        self.debug_messenger = debug_messenger;
+        self
+    }
+
+    /// Sets priorities of queues which should be created by game engine.
+    pub fn with_queue_priorities(mut self, queue_priorities: QueuePriorities) -> Self {
+        self.queue_priorities = queue_priorities;
+        self
+    }
+
+    /// Sets name of the engine which is reported to the driver and tools (e.g. RenderDoc).
+    ///
+    /// If not set, [`ENGINE_NAME`] is reported.
+    ///
+    pub fn with_engine_name(mut self, engine_name: String) -> Self {
+        self.engine_name = Some(engine_name);
+        self
+    }
+
+    /// Sets version of the engine which is reported to the driver and tools.
+    ///
+    /// If not set, [`ENGINE_VERSION`] is reported.
+    ///
+    pub fn with_engine_version(mut self, engine_version: Version) -> Self {
+        self.engine_version = Some(engine_version);
+        self
+    }
+
+    /// Sets if instance creation should be retried with reduced requirements on failure.
+    ///
+    /// If enabled, optional validation layer and `VK_EXT_debug_utils` extension are stripped
+    /// when instance creation fails because they are not present, so the game still starts
+    /// without validation. Required surface extensions are never stripped.
+    ///
+    pub fn with_instance_retry(mut self, instance_retry: bool) -> Self {
+        self.instance_retry = instance_retry;
+        self
+    }
+
     /// Name of your game.
     pub fn name(&self) -> &str {
         &self.name
@@ -38,17 +339,98 @@ pub fn version(&self) -> &Version {
     }
 
     /// If game will use validation (useful for debugging).
+    ///
+    /// Validation usage set explicitly by the configuration takes precedence,
+    /// then [`VALIDATION_VAR`] environment variable (`0` or `1`) is read,
+    /// and validation is enabled in debug builds by default.
+    /// Environment variable allows to toggle validation without recompiling (e.g. for profiling).
+    ///
     pub fn enable_validation(&self) -> bool {
-        self.enable_validation
+        let env_value = std::env::var(VALIDATION_VAR).ok();
+        self::resolve_validation(self.enable_validation, env_value.as_deref())
+    }
+
+    /// Optional features of validation which should be enabled by game engine.
+    pub fn validation_features(&self) -> &ValidationFeatures {
+        &self.validation_features
+    }
+
+    /// Physical device which should be preferred by game engine, if any.
+    pub fn preferred_device(&self) -> Option<&DeviceSelector> {
+        self.preferred_device.as_ref()
+    }
+
+    /// Optional device features which should be enabled by game engine.
+    pub fn device_features(&self) -> &DeviceFeatureChain {
+        &self.device_features
+    }
+
+    /// Highest version of Vulkan API which could be used by game engine, if set.
+    pub fn max_api_version(&self) -> Option<vulkano::Version> {
+        self.max_api_version
+    }
+
+    /// Setting of debug messenger which should be installed by game engine.
+    pub fn debug_messenger(&self) -> DebugMessengerSetting {
+        self.debug_messenger
+    }
+
+    /// Priorities of queues which should be created by game engine.
+    pub fn queue_priorities(&self) -> &QueuePriorities {
+        &self.queue_priorities
+    }
+
+    /// Name of the engine which is reported on instance creation.
+    pub fn engine_name(&self) -> &str {
+        self.engine_name.as_deref().unwrap_or(ENGINE_NAME)
+    }
+
+    /// Semver version of the engine which is reported on instance creation.
+    pub fn engine_version(&self) -> &Version {
+        self.engine_version.as_ref().unwrap_or(&*ENGINE_VERSION)
+    }
+
+    /// If instance creation is retried with reduced requirements on failure.
+    pub fn instance_retry(&self) -> bool {
+        self.instance_retry
+    }
+
+    /// Version of Vulkan API which is requested on instance creation.
+    ///
+    /// This is [`MAX_API_VERSION`] capped by [max API version](Config::max_api_version)
+    /// of this configuration.
+    ///
+    pub fn requested_api_version(&self) -> vulkano::Version {
+        match self.max_api_version {
+            Some(max_api_version) => max_api_version.min(MAX_API_VERSION),
+            None => MAX_API_VERSION,
+        }
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self::new(
-            "Hello World".to_string(),
-            Version::new(0, 0, 0),
-            cfg!(debug_assertions),
-        )
+        Self::with_default_validation("Hello World".to_string(), Version::new(0, 0, 0))
+    }
+}
+
+/// Chooses validation usage: explicit setting, then value of environment variable,
+/// then enabled in debug builds.
+fn resolve_validation(explicit: Option<bool>, env_value: Option<&str>) -> bool {
+    if let Some(enable_validation) = explicit {
+        return enable_validation;
+    }
+    match env_value.map(str::trim) {
+        Some("1") => true,
+        Some("0") => false,
+        Some(value) => {
+            log::warn!(
+                "{} variable has invalid value {:?}, expected 0 or 1",
+                VALIDATION_VAR,
+                value,
+            );
+            cfg!(debug_assertions)
+        }
+        None => cfg!(debug_assertions),
     }
 }
@@ -0,0 +1,108 @@
+#![cfg(test)]
+
+use vulkano::device::physical::PhysicalDeviceType;
+
+use super::*;
+
+const DEVICES: [(&str, PhysicalDeviceType); 3] = [
+    ("llvmpipe (LLVM 12.0.0, 256 bits)", PhysicalDeviceType::Cpu),
+    ("Intel(R) UHD Graphics", PhysicalDeviceType::IntegratedGpu),
+    ("NVIDIA GeForce GTX 1050", PhysicalDeviceType::DiscreteGpu),
+];
+
+fn matched(selector: &DeviceSelector) -> Vec<usize> {
+    DEVICES
+        .iter()
+        .enumerate()
+        .filter(|(index, (name, ty))| selector.matches(*index, name, *ty))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[test]
+fn test_index_selector() {
+    assert_eq!(matched(&DeviceSelector::Index(1)), [1]);
+    assert!(matched(&DeviceSelector::Index(DEVICES.len())).is_empty());
+}
+
+#[test]
+fn test_name_selector() {
+    let selector = DeviceSelector::Name("GeForce".to_string());
+    assert_eq!(matched(&selector), [2]);
+
+    let selector = DeviceSelector::Name("Radeon".to_string());
+    assert!(matched(&selector).is_empty());
+}
+
+#[test]
+fn test_type_selector() {
+    let selector = DeviceSelector::Type(PhysicalDeviceType::IntegratedGpu);
+    assert_eq!(matched(&selector), [1]);
+
+    let selector = DeviceSelector::Type(PhysicalDeviceType::VirtualGpu);
+    assert!(matched(&selector).is_empty());
+}
+
+#[test]
+fn test_config_preferred_device() {
+    let config = Config::default();
+    assert_eq!(config.preferred_device(), None);
+
+    let selector = DeviceSelector::Type(PhysicalDeviceType::DiscreteGpu);
+    let config = config.with_preferred_device(selector.clone());
+    assert_eq!(config.preferred_device(), Some(&selector));
+}
+
+#[test]
+fn test_requested_api_version() {
+    let config = Config::default();
+    assert_eq!(config.max_api_version(), None);
+    assert_eq!(config.requested_api_version(), MAX_API_VERSION);
+
+    let config = Config::default().with_max_api_version(vulkano::Version::V1_1);
+    assert_eq!(config.requested_api_version(), vulkano::Version::V1_1);
+
+    let config = Config::default().with_max_api_version(vulkano::Version::major_minor(1, 3));
+    assert_eq!(config.requested_api_version(), MAX_API_VERSION);
+}
+
+#[test]
+fn test_debug_messenger() {
+    let config = Config::default();
+    assert_eq!(
+        config.debug_messenger().is_enabled(),
+        cfg!(debug_assertions)
+    );
+
+    let config = config.with_debug_messenger(DebugMessengerSetting::Disabled);
+    assert!(!config.debug_messenger().is_enabled());
+    assert!(DebugSeverity::Verbose < DebugSeverity::Error);
+}
+
+#[test]
+fn test_engine_identity() {
+    let config = Config::default();
+    assert_eq!(config.engine_name(), ENGINE_NAME);
+    assert_eq!(config.engine_version(), &*ENGINE_VERSION);
+
+    let version = Version::new(2, 1, 0);
+    let config = config
+        .with_engine_name("Forked Engine".to_string())
+        .with_engine_version(version.clone());
+    assert_eq!(config.engine_name(), "Forked Engine");
+    assert_eq!(config.engine_version(), &version);
+}
+
+#[test]
+fn test_resolve_validation() {
+    // Explicit setting takes precedence over environment variable.
+    assert!(resolve_validation(Some(true), Some("0")));
+    assert!(!resolve_validation(Some(false), Some("1")));
+
+    assert!(resolve_validation(None, Some("1")));
+    assert!(!resolve_validation(None, Some("0")));
+
+    let default = cfg!(debug_assertions);
+    assert_eq!(resolve_validation(None, None), default);
+    assert_eq!(resolve_validation(None, Some("yes")), default);
+}
@@ -1,23 +1,90 @@
 //! Graphics debugging utilities for game engine.
 
+use std::ffi::CString;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 
 use log::Level;
+use vulkano::device::DeviceOwned;
 use vulkano::instance::debug::{
     DebugCallback, DebugCallbackCreationError, Message, MessageSeverity, MessageType,
 };
 use vulkano::instance::Instance;
+use vulkano::VulkanObject;
 
-/// Create debug callback for validation via Vulkan SDK.
+use crate::{config::DebugSeverity, graphics::renderer::error::ObjectNameError};
+
+/// Debug callback with minimal severity of reported messages which can be changed at runtime.
+pub struct DebugMessenger {
+    _debug_callback: DebugCallback,
+    min_severity: Arc<AtomicU8>,
+}
+
+impl DebugMessenger {
+    /// Create debug callback for validation via Vulkan SDK.
+    ///
+    /// Callback receives messages of all severities from the driver,
+    /// but messages below minimal severity are dropped by the callback itself,
+    /// so minimal severity can be changed without recreation of the callback.
+    ///
+    pub fn new(
+        instance: &Arc<Instance>,
+        min_severity: DebugSeverity,
+        ty: MessageType,
+    ) -> Result<Self, DebugCallbackCreationError> {
+        let min_severity = Arc::new(AtomicU8::new(min_severity as u8));
+        let debug_callback = {
+            let min_severity = min_severity.clone();
+            DebugCallback::new(instance, MessageSeverity::all(), ty, move |message| {
+                let severity = self::severity(message.severity);
+                // Callback may be called from any thread of the driver.
+                if severity as u8 >= min_severity.load(Ordering::Relaxed) {
+                    self::user_callback(message)
+                }
+            })?
+        };
+        Ok(Self {
+            _debug_callback: debug_callback,
+            min_severity,
+        })
+    }
+
+    /// Sets minimal severity of messages which are reported by the callback.
+    pub fn set_min_severity(&self, min_severity: DebugSeverity) {
+        self.min_severity
+            .store(min_severity as u8, Ordering::Relaxed);
+    }
+}
+
+/// Sets debug name of the Vulkan object (e.g. semaphore, fence or queue)
+/// which is shown by validation messages and in captures of tools like RenderDoc.
 ///
-/// Note that Khronos validation layer must be enabled.
+/// Name is set only if `VK_EXT_debug_utils` extension is enabled on the instance,
+/// otherwise this function does nothing.
 ///
-pub fn create_debug_callback(
-    instance: &Arc<Instance>,
-    severity: MessageSeverity,
-    ty: MessageType,
-) -> Result<DebugCallback, DebugCallbackCreationError> {
-    DebugCallback::new(instance, severity, ty, self::user_callback)
+pub fn set_object_name<T>(object: &T, name: &str) -> Result<(), ObjectNameError>
+where
+    T: VulkanObject + DeviceOwned,
+{
+    let device = object.device();
+    if !device.instance().enabled_extensions().ext_debug_utils {
+        return Ok(());
+    }
+    let name = CString::new(name).map_err(|_| ObjectNameError::NameNul)?;
+    device.set_object_name(object, &name)?;
+    Ok(())
+}
+
+/// Converts severity flags of the message into its severity.
+fn severity(severity: MessageSeverity) -> DebugSeverity {
+    match severity {
+        MessageSeverity { error: true, .. } => DebugSeverity::Error,
+        MessageSeverity { warning: true, .. } => DebugSeverity::Warning,
+        MessageSeverity {
+            information: true, ..
+        } => DebugSeverity::Info,
+        _ => DebugSeverity::Verbose,
+    }
 }
 
 /// The actual callback validation function.
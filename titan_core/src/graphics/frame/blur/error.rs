@@ -0,0 +1,61 @@
+use thiserror::Error;
+use vulkano::command_buffer::{BuildError, CommandBufferExecError, DispatchError};
+use vulkano::format::Format;
+use vulkano::image::view::ImageViewCreationError;
+use vulkano::image::ImageCreationError;
+use vulkano::pipeline::ComputePipelineCreationError;
+use vulkano::sampler::SamplerCreationError;
+use vulkano::sync::FlushError;
+use vulkano::OomError;
+
+use crate::graphics::{
+    frame::ui_draw::error::TextureRegisterError, renderer::error::DescriptorSetCreationError,
+};
+
+#[derive(Debug, Error)]
+pub enum BlurBehindCreationError {
+    #[error("shader module allocation failure: {0}")]
+    OutOfMemory(#[from] OomError),
+
+    #[error("queue family must support compute operations")]
+    QueueFamilyNotSupported,
+
+    #[error("blurred image format {0:?} cannot be used as storage and sampled image")]
+    UnsupportedTargetFormat(Format),
+
+    #[error("compute pipeline creation failure: {0}")]
+    ComputePipelineCreation(#[from] ComputePipelineCreationError),
+
+    #[error("blur sampler creation failure: {0}")]
+    SamplerCreation(#[from] SamplerCreationError),
+}
+
+#[derive(Debug, Error)]
+pub enum BlurError {
+    #[error("command buffer allocation failure: {0}")]
+    OutOfMemory(#[from] OomError),
+
+    #[error("blurred image creation failure: {0}")]
+    ImageCreation(#[from] ImageCreationError),
+
+    #[error("blurred image view creation failure: {0}")]
+    ImageViewCreation(#[from] ImageViewCreationError),
+
+    #[error("blurred texture registration failure: {0}")]
+    TextureRegister(#[from] TextureRegisterError),
+
+    #[error("blur pass descriptor set creation failure: {0}")]
+    DescriptorSetCreation(#[from] DescriptorSetCreationError),
+
+    #[error("dispatch command failure: {0}")]
+    Dispatch(#[from] DispatchError),
+
+    #[error("blur command buffer build failure: {0}")]
+    CommandBufferBuild(#[from] BuildError),
+
+    #[error("blur command buffer execution failure: {0}")]
+    CommandBufferExec(#[from] CommandBufferExecError),
+
+    #[error("blur submission failure: {0}")]
+    Flush(#[from] FlushError),
+}
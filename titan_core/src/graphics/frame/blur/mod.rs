@@ -0,0 +1,298 @@
+use std::iter;
+use std::sync::Arc;
+
+use egui::{Pos2, Rect, TextureId};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage};
+use vulkano::descriptor_set::{DescriptorSet, PersistentDescriptorSet};
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::image::view::ImageView;
+use vulkano::image::{
+    ImageCreateFlags, ImageDimensions, ImageUsage, ImageViewAbstract, StorageImage,
+};
+use vulkano::pipeline::{ComputePipeline, PipelineBindPoint};
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+use vulkano::sync::{self, GpuFuture};
+
+use crate::{
+    graphics::{
+        frame::{
+            blur::error::{BlurBehindCreationError, BlurError},
+            ui_draw::{TextureOptions, UiDrawSystem},
+        },
+        renderer::{error::DescriptorSetCreationError, FormatSupport},
+    },
+    window::PhysicalSize,
+};
+
+pub mod error;
+
+/// Size of local work group of the blur compute shader in each dimension.
+///
+/// Must be equal to `local_size_x` and `local_size_y` of `blur.comp` shader.
+///
+const WORK_GROUP_SIZE: u32 = 16;
+
+/// Format of the blurred images.
+const TARGET_FORMAT: Format = Format::R8G8B8A8_UNORM;
+
+/// Images of one blurred region of the source image.
+struct BlurTarget {
+    /// Size of the blurred region.
+    size: PhysicalSize,
+
+    /// Image which contains result of the horizontal pass.
+    intermediate: Arc<ImageView<Arc<StorageImage>>>,
+
+    /// Image which contains result of the vertical pass.
+    output: Arc<ImageView<Arc<StorageImage>>>,
+
+    /// Identifier of the output image registered in UI.
+    texture_id: TextureId,
+}
+
+/// Helper that blurs regions of the scene which are behind UI panels.
+///
+/// Each region is blurred by separable gaussian blur (horizontal pass, then vertical pass)
+/// and the result is registered in [`UiDrawSystem`] as user texture,
+/// so it can be drawn as a background of the panel (e.g. with `egui::Image`).
+///
+pub struct BlurBehind {
+    /// Queue to blur on.
+    queue: Arc<Queue>,
+
+    /// Pipeline of one blur pass.
+    pipeline: Arc<ComputePipeline>,
+
+    /// Sampler of the source and intermediate images.
+    sampler: Arc<Sampler>,
+
+    /// Radius of the blur kernel in pixels.
+    radius: u32,
+
+    /// Blurred regions of the last blur.
+    targets: Vec<BlurTarget>,
+}
+
+impl BlurBehind {
+    /// Default radius of the blur kernel in pixels.
+    pub const DEFAULT_RADIUS: u32 = 8;
+
+    /// Creates new blur helper.
+    pub fn new(queue: Arc<Queue>) -> Result<Self, BlurBehindCreationError> {
+        // Check queue for compute support.
+        if !queue.family().supports_compute() {
+            return Err(BlurBehindCreationError::QueueFamilyNotSupported);
+        }
+
+        let device = queue.device().clone();
+        let support = FormatSupport::query(device.physical_device(), TARGET_FORMAT);
+        if !(support.storage && support.sampled) {
+            return Err(BlurBehindCreationError::UnsupportedTargetFormat(
+                TARGET_FORMAT,
+            ));
+        }
+        let pipeline = {
+            use crate::graphics::shader::effect::blur;
+
+            let shader_module = blur::Shader::load(device.clone())?;
+            let pipeline = ComputePipeline::new(
+                device.clone(),
+                &shader_module.main_entry_point(),
+                &(),
+                None,
+                |_| {},
+            )?;
+            Arc::new(pipeline)
+        };
+        let sampler = Sampler::new(
+            device,
+            Filter::Nearest,
+            Filter::Nearest,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+
+        Ok(Self {
+            queue,
+            pipeline,
+            sampler,
+            radius: Self::DEFAULT_RADIUS,
+            targets: Vec::new(),
+        })
+    }
+
+    /// Radius of the blur kernel in pixels.
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    /// Sets radius of the blur kernel in pixels.
+    ///
+    /// Zero radius disables blurring, so regions are just copied.
+    ///
+    pub fn set_radius(&mut self, radius: u32) {
+        self.radius = radius;
+    }
+
+    /// Blurs regions of the source image (e.g. scene color image) and registers results in UI.
+    ///
+    /// Regions are given in physical pixels of the source image.
+    /// Returns identifiers of the blurred textures in order of the regions.
+    /// Textures are reused while sizes of the regions are not changed,
+    /// so identifiers of the previous call become invalid otherwise.
+    ///
+    /// Blur is submitted to the queue of the helper and waited for,
+    /// so the source image must not be written by GPU at the moment.
+    ///
+    pub fn blur(
+        &mut self,
+        ui_draw_system: &mut UiDrawSystem,
+        source: Arc<dyn ImageViewAbstract + Send + Sync>,
+        regions: &[Rect],
+    ) -> Result<Vec<TextureId>, BlurError> {
+        use crate::graphics::shader::effect::blur;
+
+        let sizes: Vec<_> = regions
+            .iter()
+            .map(|region| {
+                PhysicalSize::new(
+                    region.width().max(1.0) as u32,
+                    region.height().max(1.0) as u32,
+                )
+            })
+            .collect();
+        let reuse_targets = self.targets.len() == sizes.len()
+            && self
+                .targets
+                .iter()
+                .zip(&sizes)
+                .all(|(target, &size)| target.size == size);
+        if !reuse_targets {
+            for target in self.targets.drain(..) {
+                ui_draw_system.unregister_texture(target.texture_id);
+            }
+            for &size in &sizes {
+                let target = self.create_target(ui_draw_system, size)?;
+                self.targets.push(target);
+            }
+        }
+
+        let mut builder = AutoCommandBufferBuilder::primary(
+            self.queue.device().clone(),
+            self.queue.family(),
+            CommandBufferUsage::OneTimeSubmit,
+        )?;
+        builder.bind_pipeline_compute(self.pipeline.clone());
+        let sigma = (self.radius as f32 / 2.0).max(1.0);
+        for (region, target) in regions.iter().zip(&self.targets) {
+            let passes = [
+                (
+                    source.clone(),
+                    target.intermediate.clone(),
+                    region.min,
+                    [1, 0],
+                ),
+                (
+                    target.intermediate.clone() as _,
+                    target.output.clone(),
+                    Pos2::ZERO,
+                    [0, 1],
+                ),
+            ];
+            for (input, output, offset, direction) in passes {
+                let descriptor_set = self.descriptor_set(input, output)?;
+                let push_constants = blur::ty::PushConstants {
+                    offset: [offset.x as i32, offset.y as i32],
+                    direction,
+                    radius: self.radius as i32,
+                    sigma,
+                };
+                let group_counts = [
+                    (target.size.width + WORK_GROUP_SIZE - 1) / WORK_GROUP_SIZE,
+                    (target.size.height + WORK_GROUP_SIZE - 1) / WORK_GROUP_SIZE,
+                    1,
+                ];
+                builder
+                    .bind_descriptor_sets(
+                        PipelineBindPoint::Compute,
+                        self.pipeline.layout().clone(),
+                        0,
+                        descriptor_set,
+                    )
+                    .push_constants(self.pipeline.layout().clone(), 0, push_constants)
+                    .dispatch(group_counts)?;
+            }
+        }
+        let command_buffer = builder.build()?;
+
+        sync::now(self.queue.device().clone())
+            .then_execute(self.queue.clone(), command_buffer)?
+            .then_signal_fence_and_flush()?
+            .wait(None)?;
+        Ok(self
+            .targets
+            .iter()
+            .map(|target| target.texture_id)
+            .collect())
+    }
+
+    /// Creates images of blurred region with given size and registers output image in UI.
+    fn create_target(
+        &self,
+        ui_draw_system: &mut UiDrawSystem,
+        size: PhysicalSize,
+    ) -> Result<BlurTarget, BlurError> {
+        let create_image = || {
+            let image = StorageImage::with_usage(
+                self.queue.device().clone(),
+                ImageDimensions::Dim2d {
+                    width: size.width,
+                    height: size.height,
+                    array_layers: 1,
+                },
+                TARGET_FORMAT,
+                ImageUsage {
+                    storage: true,
+                    sampled: true,
+                    ..ImageUsage::none()
+                },
+                ImageCreateFlags::none(),
+                iter::once(self.queue.family()),
+            )?;
+            Result::<_, BlurError>::Ok(ImageView::new(image)?)
+        };
+        let intermediate = create_image()?;
+        let output = create_image()?;
+        let (texture_id, evicted) =
+            ui_draw_system.register_texture(output.clone(), TextureOptions::default())?;
+        if let Some(evicted) = evicted {
+            log::debug!("UI texture {:?} was evicted", evicted);
+        }
+        Ok(BlurTarget {
+            size,
+            intermediate,
+            output,
+            texture_id,
+        })
+    }
+
+    /// Creates descriptor set of one blur pass.
+    fn descriptor_set(
+        &self,
+        input: Arc<dyn ImageViewAbstract + Send + Sync>,
+        output: Arc<ImageView<Arc<StorageImage>>>,
+    ) -> Result<Arc<dyn DescriptorSet + Send + Sync>, DescriptorSetCreationError> {
+        let layout = self.pipeline.layout().descriptor_set_layouts()[0].clone();
+        let mut builder = PersistentDescriptorSet::start(layout);
+        builder.add_sampled_image(input, self.sampler.clone())?;
+        builder.add_image(output)?;
+        Ok(Arc::new(builder.build()?))
+    }
+}
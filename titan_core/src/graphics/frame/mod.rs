@@ -1,3 +1,6 @@
+pub mod blur;
 pub mod object_draw;
 pub mod system;
+pub mod ui_capture;
 pub mod ui_draw;
+pub mod ui_supersample;
@@ -20,7 +20,7 @@
         renderer::error::DescriptorSetCreationError,
         vertex::Vertex,
     },
-    window::Size,
+    window::PhysicalSize,
 };
 
 pub mod error;
@@ -131,7 +131,7 @@ pub fn new(
     /// Builds a secondary command buffer that draws game objects on the current subpass.
     pub fn draw<B>(
         &mut self,
-        viewport_size: Size,
+        viewport_size: PhysicalSize,
         uniform_buffer: Arc<B>,
     ) -> Result<SecondaryAutoCommandBuffer, ObjectDrawError>
     where
@@ -13,7 +13,7 @@
 
 use error::{DrawPassExecuteError, FrameCreationError, FrameSystemCreationError, NextPassError};
 
-use crate::{graphics::utils, window::Size};
+use crate::{graphics::utils, window::PhysicalSize};
 
 pub mod error;
 
@@ -266,8 +266,8 @@ pub fn execute<C>(&mut self, secondary_command_buffer: C) -> Result<(), DrawPass
     }
 
     /// Returns the dimensions in pixels of the viewport.
-    pub fn viewport_size(&self) -> Size {
+    pub fn viewport_size(&self) -> PhysicalSize {
         let dimensions = self.frame.framebuffer.dimensions();
-        Size::new(dimensions[0], dimensions[1])
+        PhysicalSize::new(dimensions[0], dimensions[1])
     }
 }
@@ -0,0 +1,68 @@
+use thiserror::Error;
+use vulkano::buffer::cpu_access::ReadLockError;
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilderContextError, BeginRenderPassError, BuildError, CommandBufferExecError,
+    CopyBufferImageError, ExecuteCommandsError,
+};
+use vulkano::image::view::ImageViewCreationError;
+use vulkano::image::ImageCreationError;
+use vulkano::memory::DeviceMemoryAllocError;
+use vulkano::render_pass::{FramebufferCreationError, RenderPassCreationError};
+use vulkano::sync::FlushError;
+use vulkano::OomError;
+
+use crate::graphics::frame::ui_draw::error::{UiDrawError, UiDrawSystemCreationError};
+
+#[derive(Debug, Error)]
+pub enum UiCaptureCreationError {
+    #[error("render pass creation failure: {0}")]
+    RenderPassCreation(#[from] RenderPassCreationError),
+
+    #[error("UI draw system creation failure: {0}")]
+    UiDrawSystemCreation(#[from] UiDrawSystemCreationError),
+}
+
+#[derive(Debug, Error)]
+pub enum UiCaptureError {
+    #[error("command buffer allocation failure: {0}")]
+    OutOfMemory(#[from] OomError),
+
+    #[error("offscreen image creation failure: {0}")]
+    ImageCreation(#[from] ImageCreationError),
+
+    #[error("offscreen image view creation failure: {0}")]
+    ImageViewCreation(#[from] ImageViewCreationError),
+
+    #[error("offscreen framebuffer creation failure: {0}")]
+    FramebufferCreation(#[from] FramebufferCreationError),
+
+    #[error("readback buffer allocation failure: {0}")]
+    BufferAllocation(#[from] DeviceMemoryAllocError),
+
+    #[error("UI draw failure: {0}")]
+    UiDraw(#[from] UiDrawError),
+
+    #[error("begin render pass command failure: {0}")]
+    BeginRenderPass(#[from] BeginRenderPassError),
+
+    #[error("UI command buffer execution failure: {0}")]
+    ExecuteCommands(#[from] ExecuteCommandsError),
+
+    #[error("end render pass command failure: {0}")]
+    WrongUsage(#[from] AutoCommandBufferBuilderContextError),
+
+    #[error("copy image to buffer command failure: {0}")]
+    CopyImageToBuffer(#[from] CopyBufferImageError),
+
+    #[error("capture command buffer build failure: {0}")]
+    CommandBufferBuild(#[from] BuildError),
+
+    #[error("capture command buffer execution failure: {0}")]
+    CommandBufferExec(#[from] CommandBufferExecError),
+
+    #[error("capture submission failure: {0}")]
+    Flush(#[from] FlushError),
+
+    #[error("readback buffer is locked: {0}")]
+    ReadLock(#[from] ReadLockError),
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use egui::{ClippedMesh, Texture};
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage, SubpassContents};
+use vulkano::device::Queue;
+use vulkano::format::{ClearValue, Format};
+use vulkano::image::view::ImageView;
+use vulkano::image::{AttachmentImage, ImageUsage};
+use vulkano::render_pass::{Framebuffer, RenderPass, Subpass};
+use vulkano::sync::{self, GpuFuture};
+
+use crate::{
+    graphics::frame::{
+        ui_capture::error::{UiCaptureCreationError, UiCaptureError},
+        ui_draw::UiDrawSystem,
+    },
+    window::PhysicalSize,
+};
+
+pub mod error;
+
+/// Format of the offscreen image which UI is rendered into.
+const CAPTURE_FORMAT: Format = Format::R8G8B8A8_SRGB;
+
+/// System that renders UI into an offscreen image and reads it back to CPU.
+///
+/// Render pass of this system is not compatible with the render pass of the frame,
+/// so it has its own [`UiDrawSystem`]: user textures must be registered in it separately.
+///
+pub struct UiCapture {
+    /// Queue to render and read back on.
+    graphics_queue: Arc<Queue>,
+
+    /// Render pass with single color attachment used for the capture.
+    render_pass: Arc<RenderPass>,
+
+    /// System that draws UI in the render pass of the capture.
+    ui_draw_system: UiDrawSystem,
+}
+
+impl UiCapture {
+    /// Creates new UI capture system.
+    pub fn new(graphics_queue: Arc<Queue>) -> Result<Self, UiCaptureCreationError> {
+        let render_pass = Arc::new(vulkano::single_pass_renderpass!(
+            graphics_queue.device().clone(),
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: Store,
+                    format: CAPTURE_FORMAT,
+                    samples: 1,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        )?);
+        let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+        let ui_draw_system = UiDrawSystem::new(graphics_queue.clone(), subpass, 1)?;
+
+        Ok(Self {
+            graphics_queue,
+            render_pass,
+            ui_draw_system,
+        })
+    }
+
+    /// System that draws UI into the offscreen image.
+    pub fn ui_draw_system(&mut self) -> &mut UiDrawSystem {
+        &mut self.ui_draw_system
+    }
+
+    /// Renders UI into the offscreen image of given extent and reads it back.
+    ///
+    /// Returns pixels in RGBA8 (sRGB) format, rows are tightly packed
+    /// from top to bottom without any padding (so row length is `4 * extent.width` bytes).
+    /// Background of the image is fully transparent.
+    ///
+    pub fn render(
+        &mut self,
+        meshes: Vec<ClippedMesh>,
+        texture: Arc<Texture>,
+        extent: PhysicalSize,
+        scale_factor: f32,
+    ) -> Result<Vec<u8>, UiCaptureError> {
+        let device = self.graphics_queue.device().clone();
+        let image = AttachmentImage::with_usage(
+            device.clone(),
+            extent.into(),
+            CAPTURE_FORMAT,
+            ImageUsage {
+                color_attachment: true,
+                transfer_source: true,
+                ..ImageUsage::none()
+            },
+        )?;
+        let framebuffer = Arc::new(
+            Framebuffer::start(self.render_pass.clone())
+                .add(ImageView::new(image.clone())?)?
+                .build()?,
+        );
+        let pixel_count = extent.width as usize * extent.height as usize;
+        let buffer = CpuAccessibleBuffer::from_iter(
+            device.clone(),
+            BufferUsage::transfer_destination(),
+            false,
+            (0..pixel_count * 4).map(|_| 0u8),
+        )?;
+
+        let ui_command_buffer =
+            self.ui_draw_system
+                .draw(extent, scale_factor, meshes, texture, 0, None)?;
+        let mut builder = AutoCommandBufferBuilder::primary(
+            device.clone(),
+            self.graphics_queue.family(),
+            CommandBufferUsage::OneTimeSubmit,
+        )?;
+        builder
+            .begin_render_pass(
+                framebuffer,
+                SubpassContents::SecondaryCommandBuffers,
+                [ClearValue::Float([0.0, 0.0, 0.0, 0.0])],
+            )?
+            .execute_commands(ui_command_buffer)?
+            .end_render_pass()?
+            .copy_image_to_buffer(image, buffer.clone())?;
+        let command_buffer = builder.build()?;
+
+        sync::now(device)
+            .then_execute(self.graphics_queue.clone(), command_buffer)?
+            .then_signal_fence_and_flush()?
+            .wait(None)?;
+        let pixels = buffer.read()?.to_vec();
+        Ok(pixels)
+    }
+}
@@ -0,0 +1,57 @@
+//! Cache of descriptor sets of user textures for UI rendering.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use vulkano::descriptor_set::DescriptorSet;
+
+use super::texture_array::SampledImage;
+
+/// Identity of sampled image: addresses of its image view and sampler.
+pub type SampledImageKey = (usize, usize);
+
+/// Retrieves identity of sampled image.
+pub fn sampled_image_key((image_view, sampler): &SampledImage) -> SampledImageKey {
+    let image_view = Arc::as_ptr(image_view) as *const () as usize;
+    let sampler = Arc::as_ptr(sampler) as usize;
+    (image_view, sampler)
+}
+
+/// Cache of descriptor sets keyed by identity of sampled image.
+///
+/// Cached descriptor set holds its image view and sampler alive,
+/// so their addresses cannot be reused by other objects while the set is cached.
+///
+pub struct DescriptorSetCache<S = Arc<dyn DescriptorSet + Send + Sync>> {
+    /// Cached descriptor sets.
+    sets: HashMap<SampledImageKey, S>,
+}
+
+impl<S> Default for DescriptorSetCache<S> {
+    fn default() -> Self {
+        Self {
+            sets: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Clone> DescriptorSetCache<S> {
+    /// Retrieves cached descriptor set with given key or builds and caches new one.
+    pub fn get_or_try_insert_with<E>(
+        &mut self,
+        key: SampledImageKey,
+        build: impl FnOnce() -> Result<S, E>,
+    ) -> Result<S, E> {
+        if let Some(set) = self.sets.get(&key) {
+            return Ok(set.clone());
+        }
+        let set = build()?;
+        self.sets.insert(key, set.clone());
+        Ok(set)
+    }
+
+    /// Removes all cached descriptor sets.
+    pub fn clear(&mut self) {
+        self.sets.clear();
+    }
+}
@@ -1,9 +1,14 @@
 use thiserror::Error;
-use vulkano::command_buffer::{BuildError, DrawIndexedError};
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilderContextError, BeginRenderPassError, BuildError, CommandBufferExecError,
+    DrawIndexedError, ExecuteCommandsError, ResetQueryPoolError, WriteTimestampError,
+};
+use vulkano::format::Format;
 use vulkano::image::view::ImageViewCreationError;
 use vulkano::image::ImageCreationError;
 use vulkano::memory::DeviceMemoryAllocError;
 use vulkano::pipeline::GraphicsPipelineCreationError;
+use vulkano::query::{GetResultsError, QueryPoolCreationError};
 use vulkano::sampler::SamplerCreationError;
 use vulkano::sync::FlushError;
 use vulkano::OomError;
@@ -18,11 +23,29 @@ pub enum UiDrawSystemCreationError {
     #[error("queue family must support graphics operations")]
     QueueFamilyNotSupported,
 
+    #[error("UI texture format {0:?} cannot be sampled by the device")]
+    UnsupportedTextureFormat(Format),
+
+    #[error("color attachment format {0:?} does not support blending")]
+    FormatNotBlendable(Format),
+
+    #[error("subpass has {found} color attachments, but {expected} were expected")]
+    ColorAttachmentCountMismatch { expected: usize, found: usize },
+
     #[error("graphics pipeline creation failure: {0}")]
     GraphicsPipelineCreation(#[from] GraphicsPipelineCreationError),
 
     #[error("texture sampler creation failure: {0}")]
     SamplerCreation(#[from] SamplerCreationError),
+
+    #[error("placeholder texture creation failure: {0}")]
+    ImageCreation(#[from] ImageCreationError),
+
+    #[error("placeholder texture creation failure on waiting: {0}")]
+    WaitOnImageCreation(#[from] FlushError),
+
+    #[error("placeholder texture view creation failure: {0}")]
+    ImageViewCreation(#[from] ImageViewCreationError),
 }
 
 #[derive(Debug, Error)]
@@ -50,4 +73,73 @@ pub enum UiDrawError {
 
     #[error("draw command buffer build failure: {0}")]
     CommandBufferBuild(#[from] BuildError),
+
+    #[error("meshes with MSDF textures cannot be drawn instanced")]
+    MsdfNotInstanced,
+
+    #[error("textures were changed since UI draw system was prepared")]
+    NotPrepared,
+
+    #[error("timestamp query reset command failure: {0}")]
+    ResetQueryPool(#[from] ResetQueryPoolError),
+
+    #[error("write timestamp command failure: {0}")]
+    WriteTimestamp(#[from] WriteTimestampError),
+
+    #[error("timestamp query results retrieval failure: {0}")]
+    QueryResults(#[from] GetResultsError),
+}
+
+#[derive(Debug, Error)]
+pub enum GpuTimingError {
+    #[error("queue family does not support timestamp queries")]
+    Unsupported,
+
+    #[error("timestamp query pool creation failure: {0}")]
+    QueryPoolCreation(#[from] QueryPoolCreationError),
+}
+
+#[derive(Debug, Error)]
+pub enum UiSubmitError {
+    #[error("command buffer allocation failure: {0}")]
+    OutOfMemory(#[from] OomError),
+
+    #[error("begin render pass command failure: {0}")]
+    BeginRenderPass(#[from] BeginRenderPassError),
+
+    #[error("UI command buffer execution failure: {0}")]
+    ExecuteCommands(#[from] ExecuteCommandsError),
+
+    #[error("end render pass command failure: {0}")]
+    WrongUsage(#[from] AutoCommandBufferBuilderContextError),
+
+    #[error("submit command buffer build failure: {0}")]
+    CommandBufferBuild(#[from] BuildError),
+
+    #[error("submit command buffer execution failure: {0}")]
+    CommandBufferExec(#[from] CommandBufferExecError),
+
+    #[error("UI submission failure: {0}")]
+    Flush(#[from] FlushError),
+}
+
+#[derive(Debug, Error)]
+pub enum UiSrgbViewError {
+    #[error("image format {0:?} has no sRGB counterpart")]
+    NoSrgbFormat(Format),
+
+    #[error("sRGB image view creation failure: {0}")]
+    ImageViewCreation(#[from] ImageViewCreationError),
+}
+
+#[derive(Debug, Error)]
+pub enum TextureRegisterError {
+    #[error("texture of format {0:?} cannot be sampled in UI")]
+    UnsupportedFormat(Format),
+
+    #[error("texture array has no free elements")]
+    TextureArrayFull,
+
+    #[error("sampled image descriptor set creation failure: {0}")]
+    DescriptorSetCreation(#[from] DescriptorSetCreationError),
 }
@@ -0,0 +1,164 @@
+//! GPU time measurement of UI draws with timestamp queries.
+
+use std::sync::Arc;
+
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer,
+    SecondaryAutoCommandBuffer,
+};
+use vulkano::device::Queue;
+use vulkano::query::{QueryPool, QueryResultFlags, QueryType};
+use vulkano::sync::PipelineStage;
+
+use super::error::{GpuTimingError, UiDrawError};
+
+/// State of timestamp queries of one frame in flight.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum SlotState {
+    /// Queries were never used.
+    Unused,
+
+    /// Queries are reset (or will be reset before the draw is executed).
+    Reset,
+
+    /// Timestamps were written by the draw.
+    Written,
+}
+
+/// Timestamp queries which surround UI draws of each frame in flight.
+pub struct GpuTiming {
+    /// Pool with two queries (begin and end) for each frame in flight.
+    query_pool: Arc<QueryPool>,
+
+    /// Count of nanoseconds per timestamp tick.
+    timestamp_period: f32,
+
+    /// Count of meaningful bits of timestamps written on the queue.
+    valid_bits: u32,
+
+    /// State of queries of each frame in flight.
+    slots: Vec<SlotState>,
+
+    /// GPU time of the last UI draw which results were read back.
+    last_time_ns: Option<f64>,
+}
+
+impl GpuTiming {
+    /// Creates timestamp queries for each frame in flight.
+    pub fn new(queue: &Queue, frames_in_flight: usize) -> Result<Self, GpuTimingError> {
+        let valid_bits = queue
+            .family()
+            .timestamp_valid_bits()
+            .ok_or(GpuTimingError::Unsupported)?;
+        let device = queue.device().clone();
+        let timestamp_period = device.physical_device().properties().timestamp_period;
+        let query_pool = QueryPool::new(device, QueryType::Timestamp, 2 * frames_in_flight as u32)?;
+        Ok(Self {
+            query_pool,
+            timestamp_period,
+            valid_bits,
+            slots: vec![SlotState::Unused; frames_in_flight],
+            last_time_ns: None,
+        })
+    }
+
+    /// GPU time of the last UI draw which results were read back.
+    pub fn last_time_ns(&self) -> Option<f64> {
+        self.last_time_ns
+    }
+
+    /// Reads back results of the previous use of the frame queries,
+    /// then creates command buffer which resets them.
+    pub fn reset(
+        &mut self,
+        queue: &Arc<Queue>,
+        frame_index: usize,
+    ) -> Result<PrimaryAutoCommandBuffer, UiDrawError> {
+        let slot = frame_index % self.slots.len();
+        let queries = self.queries(slot);
+        if self.slots[slot] == SlotState::Written {
+            let mut timestamps = [0_u64; 2];
+            let flags = QueryResultFlags {
+                wait: false,
+                with_availability: false,
+                partial: false,
+            };
+            let range = self.query_pool.queries_range(queries.clone()).unwrap();
+            // Results are not available yet if the frame is still executed: previous time is kept.
+            if range.get_results(&mut timestamps, flags)? {
+                let [begin, end] = timestamps;
+                let elapsed = self::elapsed_ns(begin, end, self.valid_bits, self.timestamp_period);
+                self.last_time_ns = Some(elapsed);
+            }
+        }
+
+        let mut builder = AutoCommandBufferBuilder::primary(
+            queue.device().clone(),
+            queue.family(),
+            CommandBufferUsage::OneTimeSubmit,
+        )?;
+        // SAFETY: queries of this frame are not used by other command buffers
+        // which are executed at the same time.
+        unsafe {
+            builder.reset_query_pool(self.query_pool.clone(), queries)?;
+        }
+        self.slots[slot] = SlotState::Reset;
+        Ok(builder.build()?)
+    }
+
+    /// Writes timestamp at the beginning of UI draw if queries of the frame were reset.
+    ///
+    /// Returns `true` if the timestamp was written.
+    ///
+    pub fn write_begin(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<SecondaryAutoCommandBuffer>,
+        frame_index: usize,
+    ) -> Result<bool, UiDrawError> {
+        let slot = frame_index % self.slots.len();
+        if self.slots[slot] != SlotState::Reset {
+            return Ok(false);
+        }
+        let query = self.queries(slot).start;
+        // SAFETY: query was reset before the draw.
+        unsafe {
+            builder.write_timestamp(self.query_pool.clone(), query, PipelineStage::TopOfPipe)?;
+        }
+        Ok(true)
+    }
+
+    /// Writes timestamp at the end of UI draw.
+    pub fn write_end(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<SecondaryAutoCommandBuffer>,
+        frame_index: usize,
+    ) -> Result<(), UiDrawError> {
+        let slot = frame_index % self.slots.len();
+        let query = self.queries(slot).start + 1;
+        // SAFETY: query was reset before the draw.
+        unsafe {
+            builder.write_timestamp(self.query_pool.clone(), query, PipelineStage::BottomOfPipe)?;
+        }
+        self.slots[slot] = SlotState::Written;
+        Ok(())
+    }
+
+    /// Range of queries of the frame in flight.
+    fn queries(&self, slot: usize) -> std::ops::Range<u32> {
+        let begin = 2 * slot as u32;
+        begin..begin + 2
+    }
+}
+
+/// Converts difference of timestamps into nanoseconds,
+/// ignoring bits of timestamps which are not meaningful.
+pub fn elapsed_ns(begin: u64, end: u64, valid_bits: u32, timestamp_period: f32) -> f64 {
+    let mask = if valid_bits >= u64::BITS {
+        u64::MAX
+    } else {
+        (1_u64 << valid_bits) - 1
+    };
+    // Timestamp counter could wrap around between the timestamps.
+    let ticks = (end & mask).wrapping_sub(begin & mask) & mask;
+    ticks as f64 * timestamp_period as f64
+}
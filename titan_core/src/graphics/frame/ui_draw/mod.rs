@@ -1,151 +1,1654 @@
-use std::sync::Arc;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::iter;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
-use egui::{ClippedMesh, Pos2, Texture, TextureId};
+use egui::{ClippedMesh, Color32, Pos2, Rect, Texture, TextureId, Vec2};
+use epaint::{Mesh, Vertex};
 use slotmap::{DefaultKey, Key, KeyData, SlotMap};
-use vulkano::buffer::{BufferUsage, CpuBufferPool, TypedBufferAccess};
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer, CpuBufferPool, TypedBufferAccess};
 use vulkano::command_buffer::{
-    AutoCommandBufferBuilder, CommandBufferUsage, SecondaryAutoCommandBuffer,
+    AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer,
+    SecondaryAutoCommandBuffer, SubpassContents,
 };
 use vulkano::descriptor_set::{DescriptorSet, PersistentDescriptorSet};
-use vulkano::device::Queue;
-use vulkano::format::Format;
+use vulkano::device::{Device, Queue};
+use vulkano::format::{ClearValue, Format};
 use vulkano::image::view::ImageView;
-use vulkano::image::{ImageDimensions, ImageViewAbstract, ImmutableImage, MipmapsCount};
+use vulkano::image::{
+    ImageAccess, ImageDimensions, ImageViewAbstract, ImmutableImage, MipmapsCount,
+};
 use vulkano::pipeline::blend::{AttachmentBlend, BlendFactor};
+use vulkano::pipeline::vertex::BuffersDefinition;
 use vulkano::pipeline::viewport::{Scissor, Viewport};
-use vulkano::pipeline::{GraphicsPipeline, PipelineBindPoint};
-use vulkano::render_pass::Subpass;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineCreationError, PipelineBindPoint};
+use vulkano::render_pass::{FramebufferAbstract, Subpass};
 use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+use vulkano::shader::GraphicsEntryPoint;
+use vulkano::swapchain::SurfaceTransform;
 use vulkano::sync::GpuFuture;
 
 use crate::{
     graphics::{
-        frame::ui_draw::error::{UiDrawError, UiDrawSystemCreationError},
-        renderer::error::DescriptorSetCreationError,
-        vertex::UiVertex,
+        frame::ui_draw::error::{
+            GpuTimingError, TextureRegisterError, UiDrawError, UiDrawSystemCreationError,
+            UiSrgbViewError, UiSubmitError,
+        },
+        renderer::{error::DescriptorSetCreationError, FormatSupport},
+        vertex::{UiInstance, UiVertex},
     },
-    window::Size,
+    window::PhysicalSize,
 };
 
+use descriptor_cache::DescriptorSetCache;
+use gpu_timing::GpuTiming;
+use texture_array::{SampledImage, TextureArray, EGUI_INDEX, TEXTURE_ARRAY_LEN, TEXTURE_GROUP_LEN};
+
 pub mod error;
 
+mod descriptor_cache;
+mod gpu_timing;
+mod texture_array;
+
+mod tests;
+
+/// Format of `egui` base texture and placeholder texture.
+const UI_TEXTURE_FORMAT: Format = Format::R8G8B8A8_UNORM;
+
+/// Brightness in nits of value `1.0` in extended linear sRGB (scRGB) color space.
+const SCRGB_WHITE_NITS: f32 = 80.0;
+
+/// Filter which is used when sampling texture drawn in UI.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureFilter {
+    /// Nearest texel is used; useful for pixel-art textures.
+    Nearest,
+
+    /// Texels are linearly interpolated; useful for photos.
+    Linear,
+}
+
+impl Default for TextureFilter {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+/// Wrap mode which is used when sampling texture drawn in UI.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureWrapMode {
+    /// Texture coordinates are clamped to the edge of the texture.
+    ClampToEdge,
+
+    /// Texture is repeated.
+    Repeat,
+}
+
+impl Default for TextureWrapMode {
+    fn default() -> Self {
+        Self::ClampToEdge
+    }
+}
+
+/// Sampling options of texture drawn in UI.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TextureOptions {
+    /// Filter used both for magnification and minification.
+    pub filter: TextureFilter,
+
+    /// Wrap mode used for all texture coordinates.
+    pub wrap_mode: TextureWrapMode,
+}
+
+impl TextureOptions {
+    /// All possible texture sampling options.
+    const ALL: [Self; 4] = [
+        Self::new(TextureFilter::Nearest, TextureWrapMode::ClampToEdge),
+        Self::new(TextureFilter::Nearest, TextureWrapMode::Repeat),
+        Self::new(TextureFilter::Linear, TextureWrapMode::ClampToEdge),
+        Self::new(TextureFilter::Linear, TextureWrapMode::Repeat),
+    ];
+
+    /// Creates new texture sampling options.
+    pub const fn new(filter: TextureFilter, wrap_mode: TextureWrapMode) -> Self {
+        Self { filter, wrap_mode }
+    }
+}
+
+/// Direction of Y axis of the target UI is drawn into.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum YAxis {
+    /// Y axis points down, as in `egui` and in Vulkan framebuffer coordinates.
+    Down,
+
+    /// Y axis points up, so UI is flipped vertically.
+    Up,
+}
+
+impl Default for YAxis {
+    fn default() -> Self {
+        Self::Down
+    }
+}
+
+impl YAxis {
+    /// Sign of Y coordinate which is passed into vertex shader.
+    const fn sign(self) -> f32 {
+        match self {
+            Self::Down => 1.0,
+            Self::Up => -1.0,
+        }
+    }
+}
+
+/// Adjustment of final UI color, useful for debugging of color issues
+/// or for high-contrast accessibility mode.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct ColorAdjustment {
+    /// Filter which is applied before gamma.
+    filter: ColorFilter,
+
+    /// Exponent which color is raised to.
+    gamma: f32,
+
+    /// Multiplier of color which is applied after gamma.
+    brightness: f32,
+}
+
+impl Default for ColorAdjustment {
+    fn default() -> Self {
+        Self {
+            filter: ColorFilter::None,
+            gamma: 1.0,
+            brightness: 1.0,
+        }
+    }
+}
+
+/// Filter applied to final UI color, e.g. to simulate color blindness.
+///
+/// Simulation matrices (Machado et al., 2009, full severity) are applied to linear colors.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColorFilter {
+    /// Color is left untouched.
+    None,
+
+    /// Simulation of protanopia (absence of red cones).
+    Protanopia,
+
+    /// Simulation of deuteranopia (absence of green cones).
+    Deuteranopia,
+
+    /// Simulation of tritanopia (absence of blue cones).
+    Tritanopia,
+
+    /// Custom matrix in row-major order (e.g. correction of color blindness).
+    Custom([f32; 9]),
+}
+
+impl Default for ColorFilter {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl ColorFilter {
+    /// Matrix which leaves color untouched.
+    pub const IDENTITY: [f32; 9] = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+
+    /// Matrix of protanopia simulation.
+    pub const PROTANOPIA: [f32; 9] = [
+        0.152286, 1.052583, -0.204868, //
+        0.114503, 0.786281, 0.099216, //
+        -0.003882, -0.048116, 1.051998,
+    ];
+
+    /// Matrix of deuteranopia simulation.
+    pub const DEUTERANOPIA: [f32; 9] = [
+        0.367322, 0.860646, -0.227968, //
+        0.280085, 0.672501, 0.047413, //
+        -0.011820, 0.042940, 0.968881,
+    ];
+
+    /// Matrix of tritanopia simulation.
+    pub const TRITANOPIA: [f32; 9] = [
+        1.255528, -0.076749, -0.178779, //
+        -0.078411, 0.930809, 0.147602, //
+        0.004733, 0.691367, 0.303900,
+    ];
+
+    /// Matrix of the filter in row-major order.
+    pub const fn matrix(self) -> [f32; 9] {
+        match self {
+            Self::None => Self::IDENTITY,
+            Self::Protanopia => Self::PROTANOPIA,
+            Self::Deuteranopia => Self::DEUTERANOPIA,
+            Self::Tritanopia => Self::TRITANOPIA,
+            Self::Custom(matrix) => matrix,
+        }
+    }
+}
+
+/// Layout of color attachments of the subpass UI is drawn in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UiColorAttachments {
+    /// All color attachments receive UI colors with blending.
+    Color,
+
+    /// Subpass has exactly two color attachments: the first one receives UI colors
+    /// with blending, and the second one (of unsigned integer format, e.g. `R32_UINT`)
+    /// receives element ids of meshes without blending, which enables GPU-side picking.
+    ///
+    /// Element id of the mesh is its index in meshes passed to the draw call plus one,
+    /// so zero means that no UI element was drawn.
+    ///
+    ColorAndElementId,
+}
+
+impl Default for UiColorAttachments {
+    fn default() -> Self {
+        Self::Color
+    }
+}
+
+/// Blending of UI mesh colors with colors of the target.
+///
+/// Colors of UI meshes are premultiplied by alpha.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum UiBlendMode {
+    /// Mesh is drawn over the target (source over destination).
+    Alpha,
+
+    /// Mesh colors are added to the target (e.g. for glow effects).
+    Additive,
+
+    /// Target colors are multiplied by mesh colors (e.g. for shadows).
+    ///
+    /// Transparent parts of the mesh leave the target unchanged.
+    ///
+    Multiply,
+}
+
+impl Default for UiBlendMode {
+    fn default() -> Self {
+        Self::Alpha
+    }
+}
+
+impl UiBlendMode {
+    /// Blending of color attachment which receives UI colors.
+    fn attachment_blend(self) -> AttachmentBlend {
+        let alpha = AttachmentBlend {
+            color_source: BlendFactor::One,
+            ..AttachmentBlend::alpha_blending()
+        };
+        match self {
+            Self::Alpha => alpha,
+            Self::Additive => AttachmentBlend {
+                color_destination: BlendFactor::One,
+                alpha_source: BlendFactor::Zero,
+                alpha_destination: BlendFactor::One,
+                ..alpha
+            },
+            Self::Multiply => AttachmentBlend {
+                color_source: BlendFactor::DstColor,
+                ..alpha
+            },
+        }
+    }
+}
+
+/// Graphics pipeline which draws the mesh.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PipelineKind {
+    /// Pipeline with blending of given mode.
+    Blend(UiBlendMode),
+
+    /// Pipeline for meshes with MSDF textures.
+    Msdf,
+}
+
+impl PipelineKind {
+    /// Selects pipeline for the mesh with given blend mode.
+    ///
+    /// Meshes with MSDF textures are always drawn with alpha blending.
+    ///
+    fn of_mesh(msdf: bool, blend_mode: UiBlendMode) -> Self {
+        if msdf {
+            Self::Msdf
+        } else {
+            Self::Blend(blend_mode)
+        }
+    }
+}
+
+/// Budget of meshes drawn in UI by one draw call.
+///
+/// Once any limit is exceeded, remaining meshes are dropped.
+/// `egui` emits meshes roughly in draw order, so the meshes drawn on top are dropped first.
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct UiDrawBudget {
+    /// Max count of meshes to be drawn.
+    pub max_meshes: Option<usize>,
+
+    /// Max count of vertices of all meshes to be drawn.
+    pub max_vertices: Option<usize>,
+}
+
+/// Statistics of the last UI draw call.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct UiDrawStats {
+    /// Count of meshes which were dropped because the budget was exceeded.
+    pub dropped_meshes: usize,
+
+    /// Count of meshes which were drawn, including outlines of text
+    /// and excluding meshes which were skipped (e.g. fully transparent ones).
+    pub drawn_meshes: usize,
+}
+
+/// Rotation of UI in the target, which compensates pre-transform of the swapchain
+/// (e.g. on mobile devices in landscape orientation).
+///
+/// Should match current transform of the surface which is passed as pre-transform
+/// on swapchain creation: UI rotated clockwise by the same angle is rotated back
+/// by the presentation engine, so it appears upright on the display.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UiRotation {
+    /// UI is not rotated.
+    Identity,
+
+    /// UI is rotated by 90 degrees clockwise.
+    Rotate90,
+
+    /// UI is rotated by 180 degrees.
+    Rotate180,
+
+    /// UI is rotated by 270 degrees clockwise.
+    Rotate270,
+}
+
+impl Default for UiRotation {
+    fn default() -> Self {
+        Self::Identity
+    }
+}
+
+impl From<SurfaceTransform> for UiRotation {
+    /// Converts pre-transform of the swapchain into rotation of UI;
+    /// mirrored transforms are not supported, so UI is not rotated for them.
+    fn from(transform: SurfaceTransform) -> Self {
+        match transform {
+            SurfaceTransform::Rotate90 => Self::Rotate90,
+            SurfaceTransform::Rotate180 => Self::Rotate180,
+            SurfaceTransform::Rotate270 => Self::Rotate270,
+            _ => Self::Identity,
+        }
+    }
+}
+
+impl UiRotation {
+    /// Rotation matrix of normalized device coordinates in row-major order.
+    const fn matrix(self) -> [f32; 4] {
+        match self {
+            Self::Identity => [1.0, 0.0, 0.0, 1.0],
+            Self::Rotate90 => [0.0, -1.0, 1.0, 0.0],
+            Self::Rotate180 => [-1.0, 0.0, 0.0, -1.0],
+            Self::Rotate270 => [0.0, 1.0, -1.0, 0.0],
+        }
+    }
+
+    /// Rotates point in normalized device coordinates.
+    fn rotate_ndc(self, ndc: Vec2) -> Vec2 {
+        let [xx, xy, yx, yy] = self.matrix();
+        Vec2::new(xx * ndc.x + xy * ndc.y, yx * ndc.x + yy * ndc.y)
+    }
+
+    /// Size of UI which is drawn into the target of given size.
+    ///
+    /// Width and height are swapped if UI is rotated by 90 or 270 degrees.
+    ///
+    fn ui_size(self, target_size: Vec2) -> Vec2 {
+        match self {
+            Self::Identity | Self::Rotate180 => target_size,
+            Self::Rotate90 | Self::Rotate270 => Vec2::new(target_size.y, target_size.x),
+        }
+    }
+
+    /// Rotates rectangle of UI of given size into the target.
+    fn rotate_rect(self, rect: Rect, ui_size: Vec2) -> Rect {
+        let target_size = self.ui_size(ui_size);
+        let rotate = |pos: Pos2| {
+            let ndc = Vec2::new(2.0 * pos.x / ui_size.x - 1.0, 2.0 * pos.y / ui_size.y - 1.0);
+            let ndc = self.rotate_ndc(ndc);
+            Pos2::new(
+                (ndc.x + 1.0) * 0.5 * target_size.x,
+                (ndc.y + 1.0) * 0.5 * target_size.y,
+            )
+        };
+        let (a, b) = (rotate(rect.min), rotate(rect.max));
+        Rect::from_min_max(a.min(b), a.max(b))
+    }
+}
+
+/// Binding of user texture which will be used by shader.
+enum TextureBinding {
+    /// Texture has its own descriptor set.
+    DescriptorSet(Arc<dyn DescriptorSet + Send + Sync>),
+
+    /// Texture is an element of the texture array.
+    ArrayElement {
+        /// Index of the texture array.
+        array: usize,
+
+        /// Index of the element in the texture array.
+        index: u32,
+    },
+}
+
+/// Outline or drop shadow of text meshes drawn in UI.
+///
+/// Each text mesh (which uses `egui` font texture) is drawn several times
+/// in the shadow color before the mesh itself, shifted by the offset
+/// and by samples evenly distributed on the circle of the spread radius.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextOutline {
+    /// Color of the outline or shadow (premultiplied by alpha).
+    pub color: Color32,
+
+    /// Offset of the shadow in units of meshes (zero for outline).
+    pub offset: Vec2,
+
+    /// Radius of the circle around the offset on which samples are placed.
+    pub spread: f32,
+
+    /// Count of shadow draws of each text mesh.
+    pub samples: u32,
+}
+
+impl TextOutline {
+    /// Creates outline of given color and width around the text.
+    pub fn outline(color: Color32, width: f32) -> Self {
+        Self {
+            color,
+            offset: Vec2::ZERO,
+            spread: width,
+            samples: 8,
+        }
+    }
+
+    /// Creates drop shadow of given color shifted by the offset.
+    pub fn shadow(color: Color32, offset: Vec2) -> Self {
+        Self {
+            color,
+            offset,
+            spread: 0.0,
+            samples: 1,
+        }
+    }
+
+    /// Offsets of shadow draws of each text mesh.
+    fn sample_offsets(&self) -> impl Iterator<Item = Vec2> + '_ {
+        (0..self.samples).map(move |sample| {
+            let angle = std::f32::consts::TAU * sample as f32 / self.samples as f32;
+            self.offset + self.spread * Vec2::angled(angle)
+        })
+    }
+}
+
+/// Options of multi-channel signed distance field (MSDF) texture drawn in UI.
+///
+/// Meshes which use MSDF texture are drawn with the pipeline which reconstructs
+/// sharp edges from the median of three distances, so text stays crisp at any scale.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MsdfOptions {
+    /// Distance value of the edge (usually `0.5`).
+    pub threshold: f32,
+
+    /// Additional width of the edge smoothing in distance units.
+    pub smoothing: f32,
+}
+
+impl Default for MsdfOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            smoothing: 0.0,
+        }
+    }
+}
+
+/// Description of image of user texture registered to be drawn in UI.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageDescription {
+    /// Dimensions of the image.
+    pub dimensions: ImageDimensions,
+
+    /// Format of the image.
+    pub format: Format,
+}
+
+/// Transform of texture coordinates of meshes which use user texture.
+///
+/// Useful if texture is a sub-rectangle of a texture atlas: meshes reference
+/// the whole `[0, 1]` range of texture coordinates, which is mapped onto the sub-rectangle.
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UvTransform {
+    /// Scale of texture coordinates.
+    pub scale: [f32; 2],
+
+    /// Offset of texture coordinates which is added after scaling.
+    pub offset: [f32; 2],
+}
+
+impl UvTransform {
+    /// Transform which leaves texture coordinates untouched.
+    pub const IDENTITY: Self = Self {
+        scale: [1.0, 1.0],
+        offset: [0.0, 0.0],
+    };
+
+    /// Applies this transform to texture coordinates.
+    fn apply(self, uv: Pos2) -> Pos2 {
+        Pos2 {
+            x: uv.x * self.scale[0] + self.offset[0],
+            y: uv.y * self.scale[1] + self.offset[1],
+        }
+    }
+}
+
+impl Default for UvTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// User texture registered to be drawn in UI.
+struct UserTexture {
+    /// Binding of the texture that will be used by shader.
+    binding: TextureBinding,
+
+    /// Description of the image of the texture (for debugging).
+    description: ImageDescription,
+
+    /// Number of the last draw call the texture was drawn in.
+    last_drawn: AtomicU64,
+
+    /// Options of multi-channel signed distance field if the texture is MSDF atlas.
+    msdf: Option<MsdfOptions>,
+
+    /// Transform of texture coordinates of meshes which use the texture.
+    uv_transform: UvTransform,
+}
+
+/// Scratch state of recording of UI command buffers on one thread.
+///
+/// Each recorder has its own vertex and index buffers for each frame in flight,
+/// so threads which record UI in parallel do not contend for the same buffer pools.
+/// Created by [`UiDrawSystem::create_recorder`].
+///
+pub struct UiRecorder {
+    /// Vertex and index buffers for each frame in flight.
+    frame_buffers: Vec<FrameBuffers>,
+
+    /// Statistics of the last record call.
+    stats: UiDrawStats,
+}
+
+impl UiRecorder {
+    /// Returns statistics of the last record call.
+    pub fn stats(&self) -> UiDrawStats {
+        self.stats
+    }
+}
+
+/// Command buffer builder which UI draw commands are recorded into.
+///
+/// Dynamic state is set through this trait, so that its commands could be counted.
+///
+trait UiCommandBuilder {
+    /// Command buffer which is built (e.g. primary or secondary one).
+    type Level;
+
+    /// Underlying command buffer builder.
+    fn builder(&mut self) -> &mut AutoCommandBufferBuilder<Self::Level>;
+
+    /// Sets viewport of the following draw commands.
+    fn set_viewport(&mut self, viewport: Viewport) {
+        self.builder().set_viewport(0, iter::once(viewport));
+    }
+
+    /// Sets scissor of the following draw commands.
+    fn set_scissor(&mut self, scissor: Scissor) {
+        self.builder().set_scissor(0, iter::once(scissor));
+    }
+}
+
+impl<L> UiCommandBuilder for AutoCommandBufferBuilder<L> {
+    type Level = L;
+
+    fn builder(&mut self) -> &mut AutoCommandBufferBuilder<L> {
+        self
+    }
+}
+
+/// Everything needed to record draw commands of tagged meshes.
+struct RecordContext<'a> {
+    /// Buffers of the frame which is recorded.
+    frame_buffers: &'a FrameBuffers,
+
+    /// Descriptor sets of all texture arrays.
+    array_descriptor_sets: &'a [Arc<dyn DescriptorSet + Send + Sync>],
+
+    /// Number of the draw call.
+    draw_counter: u64,
+
+    /// Size of the viewport in physical pixels.
+    viewport_size: PhysicalSize,
+
+    /// Ratio between physical pixels and units of meshes.
+    scale_factor: f32,
+}
+
+/// Vertex and index buffers used by one frame in flight.
+struct FrameBuffers {
+    /// Buffer for all vertices of UI.
+    vertex_buffer: CpuBufferPool<UiVertex>,
+
+    /// Buffer for all indices of vertices in UI element.
+    index_buffer: CpuBufferPool<u32>,
+
+    /// Buffer for indices of vertices in UI element which fit in 16 bits.
+    ///
+    /// Most of `egui` meshes are small, and 16-bit indices halve index memory
+    /// (which is noticeable on bandwidth-sensitive mobile GPUs).
+    ///
+    index_buffer_u16: CpuBufferPool<u16>,
+
+    /// Buffer for per-instance attributes of instanced UI elements.
+    instance_buffer: CpuBufferPool<UiInstance>,
+
+    /// Persistently mapped buffer for all vertices of UI, reused across draws.
+    persistent_vertex_buffer: Mutex<Option<Arc<CpuAccessibleBuffer<[UiVertex]>>>>,
+}
+
+/// Indices of vertices in UI element narrowed to the smallest index type.
+#[derive(Debug, PartialEq)]
+enum MeshIndices {
+    /// All indices fit in 16 bits.
+    U16(Vec<u16>),
+
+    /// Some indices do not fit in 16 bits.
+    U32(Vec<u32>),
+}
+
+impl MeshIndices {
+    /// Narrows indices to 16 bits if all of them fit.
+    fn new(indices: Vec<u32>) -> Self {
+        if indices.iter().all(|&index| index <= u16::MAX as u32) {
+            Self::U16(indices.into_iter().map(|index| index as u16).collect())
+        } else {
+            Self::U32(indices)
+        }
+    }
+}
+
+impl FrameBuffers {
+    /// Creates buffers for one frame in flight.
+    fn new(device: &Arc<Device>) -> Self {
+        Self {
+            vertex_buffer: CpuBufferPool::vertex_buffer(device.clone()),
+            index_buffer: CpuBufferPool::new(device.clone(), BufferUsage::index_buffer()),
+            index_buffer_u16: CpuBufferPool::new(device.clone(), BufferUsage::index_buffer()),
+            instance_buffer: CpuBufferPool::vertex_buffer(device.clone()),
+            persistent_vertex_buffer: Mutex::new(None),
+        }
+    }
+
+    /// Writes vertices into the persistently mapped buffer, reusing it if vertices fit.
+    ///
+    /// Buffer grows to the next power of two if vertices do not fit. If the buffer
+    /// is still referenced by a command buffer (even if it was not submitted yet,
+    /// e.g. by the previous draw of this frame or by the cached command buffer),
+    /// new buffer of the same capacity is allocated instead of overwriting it.
+    ///
+    fn write_persistent(
+        &self,
+        device: &Arc<Device>,
+        vertices: &[UiVertex],
+    ) -> Result<Arc<CpuAccessibleBuffer<[UiVertex]>>, UiDrawError> {
+        let mut persistent = self.persistent_vertex_buffer.lock().unwrap();
+        let capacity = persistent
+            .as_ref()
+            .map_or(0, |buffer| buffer.len() as usize);
+        let capacity = self::persistent_capacity(capacity, vertices.len());
+        if let Some(buffer) = persistent
+            .as_ref()
+            .filter(|buffer| self::is_reusable(buffer, buffer.len() as usize, capacity))
+        {
+            if let Ok(mut content) = buffer.write() {
+                content[..vertices.len()].copy_from_slice(vertices);
+                return Ok(buffer.clone());
+            }
+        }
+
+        let padding = iter::repeat(UiVertex::default()).take(capacity - vertices.len());
+        let data: Vec<_> = vertices.iter().copied().chain(padding).collect();
+        let buffer = CpuAccessibleBuffer::from_iter(
+            device.clone(),
+            BufferUsage::vertex_buffer(),
+            false,
+            data.into_iter(),
+        )?;
+        *persistent = Some(buffer.clone());
+        Ok(buffer)
+    }
+
+    /// Binds index buffer of the smallest index type and records indexed draw command.
+    fn draw_indexed<L>(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<L>,
+        indices: Vec<u32>,
+        instance_count: u32,
+        vertex_offset: i32,
+    ) -> Result<(), UiDrawError> {
+        match MeshIndices::new(indices) {
+            MeshIndices::U16(indices) => {
+                let index_buffer = self.index_buffer_u16.chunk(indices)?;
+                let index_count = index_buffer.len() as u32;
+                builder.bind_index_buffer(index_buffer).draw_indexed(
+                    index_count,
+                    instance_count,
+                    0,
+                    vertex_offset,
+                    0,
+                )?;
+            }
+            MeshIndices::U32(indices) => {
+                let index_buffer = self.index_buffer.chunk(indices)?;
+                let index_count = index_buffer.len() as u32;
+                builder.bind_index_buffer(index_buffer).draw_indexed(
+                    index_count,
+                    instance_count,
+                    0,
+                    vertex_offset,
+                    0,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct UiDrawSystem {
     /// Queue to render.
     graphics_queue: Arc<Queue>,
 
-    /// Buffer for all vertices of UI.
-    vertex_buffer: Arc<CpuBufferPool<UiVertex>>,
+    /// Vertex and index buffers for each frame in flight.
+    ///
+    /// Separate buffers ensure that CPU never writes into memory
+    /// which is still read by GPU for the previous frames.
+    ///
+    frame_buffers: Vec<FrameBuffers>,
 
-    /// Buffer for all indices of vertices in UI element.
-    index_buffer: Arc<CpuBufferPool<u32>>,
+    /// Graphics pipeline used for rendering of UI.
+    pipeline: Arc<GraphicsPipeline>,
+
+    /// Graphics pipeline used for rendering of meshes with additive blending.
+    additive_pipeline: Arc<GraphicsPipeline>,
+
+    /// Graphics pipeline used for rendering of meshes with multiplicative blending.
+    multiply_pipeline: Arc<GraphicsPipeline>,
+
+    /// Graphics pipeline used for rendering of meshes with MSDF textures (e.g. scalable text).
+    msdf_pipeline: Arc<GraphicsPipeline>,
+
+    /// Graphics pipeline used for instanced rendering of repeated UI elements.
+    instanced_pipeline: Arc<GraphicsPipeline>,
+
+    /// `egui` base texture which was uploaded last.
+    ///
+    /// Texture is retained (not only its version) so that other texture
+    /// with coincidentally equal version is uploaded too.
+    ///
+    uploaded_texture: Option<Arc<Texture>>,
+
+    /// If bindless textures are used.
+    bindless: bool,
+
+    /// Arrays of textures bound at once; first element of the first array is `egui` base texture.
+    ///
+    /// If bindless textures are used, there is only one large array.
+    /// Otherwise there are small arrays which are added when previous ones are full.
+    ///
+    texture_arrays: Vec<TextureArray>,
+
+    /// Image which is bound to empty elements of texture arrays.
+    placeholder: SampledImage,
+
+    /// Collection of user textures to be drawn in UI.
+    user_textures: SlotMap<DefaultKey, UserTexture>,
+
+    /// Max count of user textures; if exceeded, least recently drawn texture is evicted.
+    texture_capacity: Option<usize>,
+
+    /// Number of the current draw call.
+    draw_counter: AtomicU64,
+
+    /// Descriptor sets of textures which have their own descriptor set,
+    /// reused when the same image view is registered again.
+    descriptor_cache: DescriptorSetCache,
+
+    /// Samplers for textures used in UI rendering for each of sampling options.
+    samplers: HashMap<TextureOptions, Arc<Sampler>>,
+
+    /// If meshes with fully transparent vertices should not be drawn.
+    skip_transparent: bool,
+
+    /// If meshes should be grouped by their textures to reduce descriptor set binds.
+    sort_by_texture: bool,
+
+    /// Outline or drop shadow of text meshes, if enabled.
+    text_outline: Option<TextOutline>,
+
+    /// If vertices should be written into persistently mapped buffers instead of pools.
+    persistent_vertex_buffers: bool,
+
+    /// Direction of Y axis of the target.
+    y_axis: YAxis,
+
+    /// If command buffer should be reused when UI was not changed since the last draw.
+    reuse_command_buffer: bool,
+
+    /// Command buffer built by the last draw with content hash of its input
+    /// and statistics of the draw.
+    cached_command_buffer: Option<(u64, Arc<SecondaryAutoCommandBuffer>, UiDrawStats)>,
+
+    /// If meshes (vertices and clip rectangles) are already in physical pixels.
+    meshes_in_physical_pixels: bool,
+
+    /// Brightness of UI white in nits when drawn into HDR target.
+    hdr_paper_white: Option<f32>,
+
+    /// Budget of meshes drawn by one draw call.
+    budget: UiDrawBudget,
+
+    /// Adjustment of final UI color.
+    color_adjustment: ColorAdjustment,
+
+    /// Rotation of UI in the target.
+    rotation: UiRotation,
+
+    /// Statistics of the last draw call.
+    stats: UiDrawStats,
+
+    /// Timestamp queries which measure GPU time of draws, if enabled.
+    gpu_timing: Option<GpuTiming>,
+}
+
+impl UiDrawSystem {
+    /// Creates new UI draw system.
+    ///
+    /// Count of frames in flight should match count of images of the swapchain
+    /// (or any other count of frames which can be processed by GPU simultaneously).
+    ///
+    /// If descriptor indexing features are enabled on the device, bindless textures are used:
+    /// all textures are stored in one descriptor array which is bound once per draw,
+    /// and the texture of each mesh is selected by the index passed with push constants.
+    /// Otherwise each texture has its own descriptor set.
+    ///
+    /// All color attachments of the subpass receive UI colors with blending.
+    ///
+    pub fn new(
+        graphics_queue: Arc<Queue>,
+        subpass: Subpass,
+        frames_in_flight: usize,
+    ) -> Result<Self, UiDrawSystemCreationError> {
+        Self::with_color_attachments(
+            graphics_queue,
+            subpass,
+            frames_in_flight,
+            UiColorAttachments::Color,
+        )
+    }
+
+    /// Creates new UI draw system which writes into color attachments of the subpass
+    /// as described by given layout (e.g. colors and element ids of meshes).
+    ///
+    /// See [`new`](UiDrawSystem::new) for details.
+    ///
+    pub fn with_color_attachments(
+        graphics_queue: Arc<Queue>,
+        subpass: Subpass,
+        frames_in_flight: usize,
+        color_attachments: UiColorAttachments,
+    ) -> Result<Self, UiDrawSystemCreationError> {
+        Self::create(
+            graphics_queue,
+            subpass,
+            frames_in_flight,
+            color_attachments,
+            None,
+        )
+    }
+
+    /// Creates new UI draw system which uses user-provided fragment shader
+    /// instead of the default one (e.g. for scanlines, CRT warp or chromatic aberration).
+    ///
+    /// Fragment shader must be compatible with the default UI fragment shader:
+    /// - inputs are `layout(location = 0) in vec4 color` (premultiplied linear color
+    ///   of the vertex) and `layout(location = 1) in vec2 uv` (texture coordinates);
+    ///   HDR paper white is not applied to the color, so the shader should multiply
+    ///   the output by `color_scale` push constant member to support HDR targets;
+    /// - output is `layout(location = 0) out vec4 outColor` (premultiplied color) only;
+    /// - push constant block must be declared by `#include <ui_push_constants.glsl>`
+    ///   with `src/graphics/shader` include directory (it's fine not to use its members);
+    /// - textures are declared as `layout(binding = 0, set = 0) uniform sampler2D textures[N]`,
+    ///   where `N` is 128 if [bindless textures](UiDrawSystem::supports_bindless) are used
+    ///   (texture is selected by `texture_index` push constant member), or 4 otherwise
+    ///   (texture is selected by constant index as in `ui.frag`).
+    ///
+    /// Meshes with MSDF textures are still drawn by the default MSDF shader.
+    /// See [`new`](UiDrawSystem::new) for details.
+    ///
+    pub fn new_with_fragment_shader(
+        graphics_queue: Arc<Queue>,
+        subpass: Subpass,
+        frames_in_flight: usize,
+        fragment_shader: GraphicsEntryPoint,
+    ) -> Result<Self, UiDrawSystemCreationError> {
+        Self::create(
+            graphics_queue,
+            subpass,
+            frames_in_flight,
+            UiColorAttachments::Color,
+            Some(fragment_shader),
+        )
+    }
+
+    fn create(
+        graphics_queue: Arc<Queue>,
+        subpass: Subpass,
+        frames_in_flight: usize,
+        color_attachments: UiColorAttachments,
+        fragment_shader: Option<GraphicsEntryPoint>,
+    ) -> Result<Self, UiDrawSystemCreationError> {
+        // Check queue for graphics support.
+        if !graphics_queue.family().supports_graphics() {
+            return Err(UiDrawSystemCreationError::QueueFamilyNotSupported);
+        }
+
+        let device = graphics_queue.device().clone();
+        if !FormatSupport::query(device.physical_device(), UI_TEXTURE_FORMAT).sampled {
+            return Err(UiDrawSystemCreationError::UnsupportedTextureFormat(
+                UI_TEXTURE_FORMAT,
+            ));
+        }
+        let color_formats = {
+            let desc = subpass.render_pass().desc();
+            let subpass_desc = &desc.subpasses()[subpass.index() as usize];
+            subpass_desc
+                .color_attachments
+                .iter()
+                .map(|&(index, _)| desc.attachments()[index].format)
+                .collect::<Vec<_>>()
+        };
+        let format_support = |format| FormatSupport::query(device.physical_device(), format);
+        let blends = |blend_mode| {
+            self::attachment_blends(
+                color_attachments,
+                blend_mode,
+                &color_formats,
+                format_support,
+            )
+        };
+        let (alpha_blends, additive_blends, multiply_blends) = (
+            blends(UiBlendMode::Alpha)?,
+            blends(UiBlendMode::Additive)?,
+            blends(UiBlendMode::Multiply)?,
+        );
+        let bindless = Self::supports_bindless(&device);
+        let (pipeline, additive_pipeline, multiply_pipeline, msdf_pipeline, instanced_pipeline) = {
+            use crate::graphics::shader::ui::{
+                bindless_fragment, element_id_bindless_fragment, element_id_fragment,
+                element_id_msdf_fragment, fragment, instanced_vertex, msdf_fragment, vertex,
+            };
+
+            let vert_shader_module = vertex::Shader::load(device.clone())?;
+            let instanced_shader_module = instanced_vertex::Shader::load(device.clone())?;
+
+            let start = |blends: &[AttachmentBlend]| {
+                GraphicsPipeline::start()
+                    .vertex_input_single_buffer::<UiVertex>()
+                    .vertex_shader(vert_shader_module.main_entry_point(), ())
+                    .triangle_list()
+                    .viewports_scissors_dynamic(1)
+                    .cull_mode_disabled()
+                    .blend_individual(blends.iter().cloned())
+                    .render_pass(subpass.clone())
+            };
+            let start_instanced = || {
+                let vertex_input = BuffersDefinition::new()
+                    .vertex::<UiVertex>()
+                    .instance::<UiInstance>();
+                GraphicsPipeline::start()
+                    .vertex_input(vertex_input)
+                    .vertex_shader(instanced_shader_module.main_entry_point(), ())
+                    .triangle_list()
+                    .viewports_scissors_dynamic(1)
+                    .cull_mode_disabled()
+                    .blend_individual(alpha_blends.iter().cloned())
+                    .render_pass(subpass.clone())
+            };
+            // Pipelines of all blend modes and instanced pipeline share the fragment shader.
+            let build = |fragment_shader: GraphicsEntryPoint| {
+                let build_blended = |blends: &[AttachmentBlend]| {
+                    start(blends)
+                        .fragment_shader(fragment_shader.clone(), ())
+                        .build(device.clone())
+                        .map(Arc::new)
+                };
+                let pipeline = build_blended(&alpha_blends)?;
+                let additive_pipeline = build_blended(&additive_blends)?;
+                let multiply_pipeline = build_blended(&multiply_blends)?;
+                let instanced_pipeline = start_instanced()
+                    .fragment_shader(fragment_shader.clone(), ())
+                    .build(device.clone())?;
+                Ok::<_, GraphicsPipelineCreationError>((
+                    pipeline,
+                    additive_pipeline,
+                    multiply_pipeline,
+                    Arc::new(instanced_pipeline),
+                ))
+            };
+            // Element id output must not be declared if the subpass has no attachment for it.
+            let element_id = color_attachments == UiColorAttachments::ColorAndElementId;
+            let (pipeline, additive_pipeline, multiply_pipeline, instanced_pipeline) =
+                match (fragment_shader, bindless, element_id) {
+                    (Some(fragment_shader), _, _) => build(fragment_shader)?,
+                    (None, true, true) => {
+                        let module = element_id_bindless_fragment::Shader::load(device.clone())?;
+                        build(module.main_entry_point())?
+                    }
+                    (None, true, false) => {
+                        let module = bindless_fragment::Shader::load(device.clone())?;
+                        build(module.main_entry_point())?
+                    }
+                    (None, false, true) => {
+                        let module = element_id_fragment::Shader::load(device.clone())?;
+                        build(module.main_entry_point())?
+                    }
+                    (None, false, false) => {
+                        let module = fragment::Shader::load(device.clone())?;
+                        build(module.main_entry_point())?
+                    }
+                };
+            let msdf_pipeline = if element_id {
+                let frag_shader_module = element_id_msdf_fragment::Shader::load(device.clone())?;
+                start(&alpha_blends)
+                    .fragment_shader(frag_shader_module.main_entry_point(), ())
+                    .build(device.clone())?
+            } else {
+                let frag_shader_module = msdf_fragment::Shader::load(device.clone())?;
+                start(&alpha_blends)
+                    .fragment_shader(frag_shader_module.main_entry_point(), ())
+                    .build(device.clone())?
+            };
+            (
+                pipeline,
+                additive_pipeline,
+                multiply_pipeline,
+                Arc::new(msdf_pipeline),
+                instanced_pipeline,
+            )
+        };
+
+        let frame_buffers = (0..frames_in_flight.max(1))
+            .map(|_| FrameBuffers::new(&device))
+            .collect();
+
+        let samplers = self::samplers(|filter, mipmap_mode, address_mode| {
+            Sampler::new(
+                device.clone(),
+                filter,
+                filter,
+                mipmap_mode,
+                address_mode,
+                address_mode,
+                address_mode,
+                0.0,
+                1.0,
+                0.0,
+                0.0,
+            )
+        })?;
+
+        let placeholder: SampledImage = {
+            let dimensions = ImageDimensions::Dim2d {
+                width: 1,
+                height: 1,
+                array_layers: 1,
+            };
+            let (image, image_future) = ImmutableImage::from_iter(
+                [u8::MAX; 4],
+                dimensions,
+                MipmapsCount::One,
+                UI_TEXTURE_FORMAT,
+                graphics_queue.clone(),
+            )?;
+            image_future.flush()?;
+            let image = ImageView::new(image)?;
+            let sampler = samplers[&TextureOptions::default()].clone();
+            (image, sampler)
+        };
+        let len = if bindless {
+            TEXTURE_ARRAY_LEN
+        } else {
+            TEXTURE_GROUP_LEN
+        };
+        let reserved = EGUI_INDEX as usize + 1;
+        let texture_arrays = vec![TextureArray::new(placeholder.clone(), len, reserved)];
+
+        Ok(Self {
+            graphics_queue,
+            frame_buffers,
+            pipeline,
+            additive_pipeline,
+            multiply_pipeline,
+            msdf_pipeline,
+            instanced_pipeline,
+            samplers,
+            descriptor_cache: DescriptorSetCache::default(),
+            uploaded_texture: None,
+            bindless,
+            texture_arrays,
+            placeholder,
+            user_textures: SlotMap::default(),
+            texture_capacity: None,
+            draw_counter: AtomicU64::new(0),
+            skip_transparent: false,
+            sort_by_texture: false,
+            text_outline: None,
+            persistent_vertex_buffers: false,
+            y_axis: YAxis::default(),
+            reuse_command_buffer: false,
+            cached_command_buffer: None,
+            meshes_in_physical_pixels: false,
+            hdr_paper_white: None,
+            budget: UiDrawBudget::default(),
+            color_adjustment: ColorAdjustment::default(),
+            rotation: UiRotation::default(),
+            stats: UiDrawStats::default(),
+            gpu_timing: None,
+        })
+    }
+
+    /// Checks if bindless textures can be used on the device.
+    ///
+    /// If so, all textures are stored in one large descriptor array.
+    ///
+    pub fn supports_bindless(device: &Device) -> bool {
+        let features = device.enabled_features();
+        features.runtime_descriptor_array && features.shader_sampled_image_array_dynamic_indexing
+    }
+
+    /// Inserts image into the first texture array with free element.
+    ///
+    /// If bindless textures are not used, new texture array is added when all arrays are full.
+    ///
+    fn insert_into_array(
+        &mut self,
+        image: SampledImage,
+    ) -> Result<TextureBinding, TextureRegisterError> {
+        let inserted =
+            self.texture_arrays
+                .iter_mut()
+                .enumerate()
+                .find_map(|(array, texture_array)| {
+                    let index = texture_array.insert(image.clone())?;
+                    Some(TextureBinding::ArrayElement { array, index })
+                });
+        if let Some(binding) = inserted {
+            return Ok(binding);
+        }
+        if self.bindless {
+            return Err(TextureRegisterError::TextureArrayFull);
+        }
+        let mut texture_array = TextureArray::new(self.placeholder.clone(), TEXTURE_GROUP_LEN, 0);
+        let index = texture_array.insert(image).unwrap();
+        self.texture_arrays.push(texture_array);
+        let array = self.texture_arrays.len() - 1;
+        Ok(TextureBinding::ArrayElement { array, index })
+    }
+
+    /// Registers new user texture to be drawn in UI
+    /// which will be sampled with provided options.
+    ///
+    /// Format of the texture must be a color format which supports sampling
+    /// with optimal tiling, so depth/stencil textures are rejected.
+    /// Block-compressed formats are accepted if supported by the device.
+    /// Textures with less than four components are expanded when sampled
+    /// (missing color components are zero and missing alpha is one),
+    /// so single-channel textures are drawn in red.
+    ///
+    /// If capacity of user textures is exceeded, least recently drawn texture
+    /// is evicted and its identifier is returned along with the new one.
+    /// Textures drawn by the current or the previous draw call are never evicted,
+    /// so capacity could be exceeded until they are no longer drawn.
+    ///
+    pub fn register_texture(
+        &mut self,
+        image_view: Arc<dyn ImageViewAbstract + Send + Sync>,
+        options: TextureOptions,
+    ) -> Result<(TextureId, Option<TextureId>), TextureRegisterError> {
+        let format = image_view.format();
+        if !self.is_sampleable(format) {
+            return Err(TextureRegisterError::UnsupportedFormat(format));
+        }
+        let description = ImageDescription {
+            dimensions: image_view.image().dimensions(),
+            format,
+        };
+        let sampler = self.samplers[&options].clone();
+        let image = (image_view, sampler);
+        // Texture is evicted only after the new one is bound successfully,
+        // so failed registration never unregisters other textures.
+        let (binding, evicted) = match self.insert_into_array(image.clone()) {
+            Ok(binding) => (binding, self.evict_texture(false)),
+            Err(TextureRegisterError::TextureArrayFull) => {
+                // Element of the array is freed only if capacity is reached.
+                let evicted = self
+                    .evict_texture(true)
+                    .ok_or(TextureRegisterError::TextureArrayFull)?;
+                let binding = self.insert_into_array(image)?;
+                (binding, Some(evicted))
+            }
+            Err(error) => return Err(error),
+        };
+        let texture_id = self.insert_texture(binding, description, None);
+        Ok((texture_id, evicted))
+    }
+
+    /// Registers new multi-channel signed distance field (MSDF) texture to be drawn in UI
+    /// which will be sampled with provided options.
+    ///
+    /// Meshes which use this texture are drawn with the MSDF pipeline,
+    /// so distances are stored in RGB channels of the texture
+    /// and vertex color is used as color of the glyph.
+    /// Texture should be sampled with linear filter.
+    ///
+    /// MSDF texture always has its own descriptor set, even if bindless textures are used.
+    ///
+    pub fn register_msdf_texture(
+        &mut self,
+        image_view: Arc<dyn ImageViewAbstract + Send + Sync>,
+        options: TextureOptions,
+        msdf: MsdfOptions,
+    ) -> Result<(TextureId, Option<TextureId>), TextureRegisterError> {
+        let format = image_view.format();
+        if !self.is_sampleable(format) {
+            return Err(TextureRegisterError::UnsupportedFormat(format));
+        }
+        let description = ImageDescription {
+            dimensions: image_view.image().dimensions(),
+            format,
+        };
+        let descriptor_set = {
+            let layout = self.msdf_pipeline.layout().descriptor_set_layouts()[0].clone();
+            let sampler = self.samplers[&options].clone();
+            let image = (image_view, sampler);
+            let key = descriptor_cache::sampled_image_key(&image);
+            self.descriptor_cache.get_or_try_insert_with(key, || {
+                let (image_view, sampler) = image;
+                let mut builder = PersistentDescriptorSet::start(layout);
+                builder.add_sampled_image(image_view, sampler)?;
+                let set: Arc<dyn DescriptorSet + Send + Sync> = Arc::new(builder.build()?);
+                Ok::<_, DescriptorSetCreationError>(set)
+            })?
+        };
+        let binding = TextureBinding::DescriptorSet(descriptor_set);
+        let evicted = self.evict_texture(false);
+        let texture_id = self.insert_texture(binding, description, Some(msdf));
+        Ok((texture_id, evicted))
+    }
+
+    /// Removes all cached descriptor sets of textures which have their own descriptor set.
+    ///
+    /// Descriptor sets are cached by identity of image view and sampler, so the same image view
+    /// registered repeatedly (e.g. transient render target) reuses its descriptor set.
+    /// Cache keeps cached image views alive, so it should be cleared to bound its growth.
+    ///
+    pub fn clear_descriptor_cache(&mut self) {
+        self.descriptor_cache.clear();
+    }
+
+    /// Drops all GPU resources of textures: user textures, descriptor sets
+    /// of texture arrays and cached ones, and `egui` base texture.
+    ///
+    /// `egui` base texture is uploaded again by the next draw,
+    /// but user textures must be registered again by the caller.
+    ///
+    /// This alone does not recover the system after the device was lost:
+    /// pipelines, samplers, the placeholder texture and the graphics queue
+    /// belong to the lost device too, so new system must be created for the new device.
+    /// Call this to release images of the lost device before the system is replaced.
+    ///
+    pub fn invalidate_gpu_resources(&mut self) {
+        self::reset_textures(
+            &mut self.user_textures,
+            &mut self.texture_arrays,
+            &mut self.uploaded_texture,
+        );
+        self.descriptor_cache.clear();
+        self.cached_command_buffer = None;
+    }
+
+    /// Inserts new user texture and returns its identifier.
+    fn insert_texture(
+        &mut self,
+        binding: TextureBinding,
+        description: ImageDescription,
+        msdf: Option<MsdfOptions>,
+    ) -> TextureId {
+        self.cached_command_buffer = None;
+        let key = self.user_textures.insert(UserTexture {
+            binding,
+            description,
+            last_drawn: AtomicU64::new(self.draw_counter.load(Ordering::Relaxed)),
+            msdf,
+            uv_transform: UvTransform::IDENTITY,
+        });
+        let id = key.data().as_ffi();
+        TextureId::User(id)
+    }
+
+    /// Sets transform of texture coordinates of meshes which use given user texture.
+    ///
+    /// Texture coordinates are transformed on CPU when vertices are uploaded,
+    /// so it is transparent for `egui`. Identity transform is used by default.
+    ///
+    pub fn set_uv_transform(&mut self, texture_id: TextureId, uv_transform: UvTransform) {
+        if let TextureId::User(id) = texture_id {
+            let key_data = KeyData::from_ffi(id);
+            let key = DefaultKey::from(key_data);
+            if let Some(texture) = self.user_textures.get_mut(key) {
+                texture.uv_transform = uv_transform;
+                self.cached_command_buffer = None;
+            }
+        }
+    }
+
+    /// Sets max count of user textures to be drawn in UI.
+    ///
+    /// When new texture is registered beyond this capacity,
+    /// least recently drawn texture is unregistered automatically.
+    /// There is no capacity by default.
+    ///
+    pub fn set_texture_capacity(&mut self, texture_capacity: Option<usize>) {
+        self.texture_capacity = texture_capacity;
+    }
+
+    /// Evicts least recently drawn user texture if capacity is reached.
+    ///
+    /// If `in_array` is set, only textures which are elements of texture arrays
+    /// are evicted, so an element is freed for the new texture.
+    ///
+    /// Textures drawn or registered since the previous draw call are never evicted,
+    /// because `egui` could still use them in the current or the next frame.
+    ///
+    fn evict_texture(&mut self, in_array: bool) -> Option<TextureId> {
+        let protected_since = self.draw_counter.load(Ordering::Relaxed).saturating_sub(1);
+        let textures = self
+            .user_textures
+            .iter()
+            .filter(|(_, texture)| {
+                !in_array || matches!(texture.binding, TextureBinding::ArrayElement { .. })
+            })
+            .map(|(key, texture)| (key, texture.last_drawn.load(Ordering::Relaxed)));
+        let key = self::eviction_candidate(
+            textures,
+            self.user_textures.len(),
+            self.texture_capacity,
+            protected_since,
+        )?;
+        self.remove_texture(key);
+        let id = key.data().as_ffi();
+        Some(TextureId::User(id))
+    }
+
+    /// Removes user texture, freeing its element of the texture array if needed.
+    fn remove_texture(&mut self, key: DefaultKey) {
+        self.cached_command_buffer = None;
+        let texture = self.user_textures.remove(key);
+        if let Some(UserTexture {
+            binding: TextureBinding::ArrayElement { array, index },
+            ..
+        }) = texture
+        {
+            self.texture_arrays[array].remove(index);
+        }
+    }
+
+    /// Sets if meshes with fully transparent vertices should not be drawn.
+    ///
+    /// Disabled by default, because such meshes still could be drawn
+    /// for their side effects (for example, on depth or stencil buffers).
+    ///
+    pub fn set_skip_transparent(&mut self, skip_transparent: bool) {
+        self.skip_transparent = skip_transparent;
+        self.cached_command_buffer = None;
+    }
+
+    /// Sets if meshes should be grouped by their textures before drawing.
+    ///
+    /// Meshes are grouped by descriptor sets of their textures (textures of one texture array
+    /// share the descriptor set) in order of the first use of each set,
+    /// and relative order of meshes within each group is preserved.
+    /// This reduces count of descriptor set binds when meshes alternate between textures,
+    /// but changes order in which meshes of different textures are drawn:
+    /// overlapping alpha-blended meshes could be drawn incorrectly.
+    /// So it is disabled by default and should be enabled only if meshes do not overlap
+    /// (or their blending does not depend on draw order).
+    ///
+    pub fn set_sort_by_texture(&mut self, sort_by_texture: bool) {
+        self.sort_by_texture = sort_by_texture;
+        self.cached_command_buffer = None;
+    }
 
-    /// Graphics pipeline used for rendering of UI.
-    pipeline: Arc<GraphicsPipeline>,
+    /// Sets outline or drop shadow of text meshes (which use `egui` font texture).
+    ///
+    /// Shadow draws reuse the pipeline of the text mesh and are recorded right before it,
+    /// so each outlined text mesh costs `samples + 1` draws.
+    /// There is no outline by default.
+    ///
+    pub fn set_text_outline(&mut self, text_outline: Option<TextOutline>) {
+        self.text_outline = text_outline;
+        self.cached_command_buffer = None;
+    }
 
-    /// Version of `egui` base texture.
-    texture_version: u64,
+    /// Sets if vertices of meshes should be written into persistently mapped buffers.
+    ///
+    /// By default, vertices of each mesh are allocated from the buffer pool on every draw.
+    /// In this mode vertices of all meshes are written at once into the buffer
+    /// of the frame in flight, which is reused across draws and grows only when needed.
+    /// This reduces allocations for UI which barely changes between frames.
+    /// Each frame in flight has its own buffer, so frame index passed to draws
+    /// must not be reused until GPU finishes the frame (as with buffer pools).
+    /// Buffer still referenced by a command buffer (e.g. by another draw of the same frame)
+    /// is never overwritten: new buffer is allocated for the draw instead.
+    ///
+    pub fn set_persistent_vertex_buffers(&mut self, persistent_vertex_buffers: bool) {
+        self.persistent_vertex_buffers = persistent_vertex_buffers;
+        self.cached_command_buffer = None;
+    }
 
-    /// Descriptor set for `egui` base texture that will be used by shader.
-    texture_descriptor_set: Option<Arc<dyn DescriptorSet + Send + Sync>>,
+    /// Sets direction of Y axis of the target UI will be drawn into.
+    pub fn set_y_axis(&mut self, y_axis: YAxis) {
+        self.y_axis = y_axis;
+        self.cached_command_buffer = None;
+    }
 
-    /// Collection of descriptor sets for user textures to be drawn in UI.
-    user_texture_descriptor_sets: SlotMap<DefaultKey, Arc<dyn DescriptorSet + Send + Sync>>,
+    /// Sets if meshes (vertices and clip rectangles) are already in physical pixels.
+    ///
+    /// By default meshes are expected in logical points (as produced by `egui`)
+    /// and are scaled by the scale factor passed to [`draw`](UiDrawSystem::draw).
+    /// If set, scale factor is ignored, which is useful when `egui` runs at physical scale.
+    ///
+    pub fn set_meshes_in_physical_pixels(&mut self, meshes_in_physical_pixels: bool) {
+        self.meshes_in_physical_pixels = meshes_in_physical_pixels;
+        self.cached_command_buffer = None;
+    }
 
-    /// A sampler for textures used in UI rendering.
-    sampler: Arc<Sampler>,
-}
+    /// Sets brightness of UI white in nits if UI is drawn into HDR target.
+    ///
+    /// Colors of UI are already converted into linear space, so when set,
+    /// they are multiplied by `paper_white / 80` in fragment shader after gamma and brightness,
+    /// where 80 nits is brightness of value `1.0` in extended linear sRGB (scRGB).
+    /// In this case attachment of the subpass should have linear floating point format
+    /// (e.g. `R16G16B16A16_SFLOAT`) which is tone-mapped later.
+    ///
+    /// `None` by default, so UI is drawn for LDR target without any scaling.
+    ///
+    pub fn set_hdr_paper_white(&mut self, hdr_paper_white: Option<f32>) {
+        self.hdr_paper_white = hdr_paper_white;
+        self.cached_command_buffer = None;
+    }
 
-impl UiDrawSystem {
-    /// Creates new UI draw system.
-    pub fn new(
-        graphics_queue: Arc<Queue>,
-        subpass: Subpass,
-    ) -> Result<Self, UiDrawSystemCreationError> {
-        // Check queue for graphics support.
-        if !graphics_queue.family().supports_graphics() {
-            return Err(UiDrawSystemCreationError::QueueFamilyNotSupported);
-        }
+    /// Sets filter of final UI color which is applied before gamma
+    /// (e.g. to simulate color blindness).
+    ///
+    /// No filter is applied by default.
+    ///
+    pub fn set_color_filter(&mut self, filter: ColorFilter) {
+        self.color_adjustment.filter = filter;
+        self.cached_command_buffer = None;
+    }
 
-        let device = graphics_queue.device().clone();
-        let pipeline = {
-            use crate::graphics::shader::ui::{fragment, vertex};
+    /// Sets exponent which final UI color is raised to.
+    ///
+    /// Gamma is applied to color which is not premultiplied by alpha,
+    /// so translucent colors are adjusted the same way as opaque ones.
+    ///
+    /// Default value `1.0` leaves color untouched.
+    ///
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.color_adjustment.gamma = gamma;
+        self.cached_command_buffer = None;
+    }
 
-            let vert_shader_module = vertex::Shader::load(device.clone())?;
-            let frag_shader_module = fragment::Shader::load(device.clone())?;
+    /// Sets multiplier of final UI color which is applied after gamma.
+    ///
+    /// Default value `1.0` leaves color untouched.
+    ///
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.color_adjustment.brightness = brightness;
+        self.cached_command_buffer = None;
+    }
 
-            let blend = AttachmentBlend {
-                color_source: BlendFactor::One,
-                ..AttachmentBlend::alpha_blending()
-            };
+    /// Sets rotation of UI in the target which compensates pre-transform of the swapchain.
+    ///
+    /// Meshes are still laid out in upright orientation: if UI is rotated by 90 or 270 degrees,
+    /// width and height of the UI are the height and width of the target respectively.
+    /// UI is not rotated by default.
+    ///
+    pub fn set_rotation(&mut self, rotation: UiRotation) {
+        self.rotation = rotation;
+        self.cached_command_buffer = None;
+    }
 
-            Arc::new(
-                GraphicsPipeline::start()
-                    .vertex_input_single_buffer::<UiVertex>()
-                    .vertex_shader(vert_shader_module.main_entry_point(), ())
-                    .fragment_shader(frag_shader_module.main_entry_point(), ())
-                    .triangle_list()
-                    .viewports_scissors_dynamic(1)
-                    .cull_mode_disabled()
-                    .blend_collective(blend)
-                    .render_pass(subpass)
-                    .build(device.clone())?,
-            )
-        };
+    /// Sets if command buffer should be reused when UI was not changed since the last draw.
+    ///
+    /// UI is considered unchanged if content hash of meshes (vertices, indices,
+    /// textures and clip rectangles), viewport size and scale factor are equal
+    /// to ones of the last draw. Change of `egui` base texture version,
+    /// registration of user textures or change of any draw option
+    /// always invalidate the command buffer.
+    ///
+    /// Reused command buffer may be executed while it is still executed by the previous frame,
+    /// so it is built with simultaneous use which could be slower on some devices.
+    /// Disabled by default.
+    ///
+    pub fn set_reuse_command_buffer(&mut self, reuse_command_buffer: bool) {
+        self.reuse_command_buffer = reuse_command_buffer;
+        self.cached_command_buffer = None;
+    }
 
-        let vertex_buffer = Arc::new(CpuBufferPool::vertex_buffer(device.clone()));
-        let index_buffer = Arc::new(CpuBufferPool::new(
-            device.clone(),
-            BufferUsage::index_buffer(),
-        ));
+    /// Sets budget of meshes drawn by one draw call.
+    ///
+    /// Meshes beyond the budget are dropped and their count is reported
+    /// by [`stats`](UiDrawSystem::stats). Unbounded by default.
+    ///
+    pub fn set_budget(&mut self, budget: UiDrawBudget) {
+        self.budget = budget;
+        self.cached_command_buffer = None;
+    }
 
-        let sampler = Sampler::new(
-            device.clone(),
-            Filter::Linear,
-            Filter::Linear,
-            MipmapMode::Linear,
-            SamplerAddressMode::ClampToEdge,
-            SamplerAddressMode::ClampToEdge,
-            SamplerAddressMode::ClampToEdge,
-            0.0,
-            1.0,
-            0.0,
-            0.0,
-        )?;
+    /// Returns statistics of the last draw call.
+    pub fn stats(&self) -> UiDrawStats {
+        self.stats
+    }
 
-        Ok(Self {
-            graphics_queue,
-            vertex_buffer,
-            index_buffer,
-            pipeline,
-            sampler,
-            texture_version: 0,
-            texture_descriptor_set: None,
-            user_texture_descriptor_sets: SlotMap::default(),
-        })
+    /// Sets if GPU time of UI draws should be measured with timestamp queries.
+    ///
+    /// Timestamps are written only into command buffers of [`draw`](UiDrawSystem::draw)
+    /// for frames which queries were reset by [`reset_gpu_timing`](UiDrawSystem::reset_gpu_timing),
+    /// and command buffer is never reused while timing is enabled.
+    /// Disabled by default.
+    ///
+    pub fn enable_gpu_timing(&mut self, enabled: bool) -> Result<(), GpuTimingError> {
+        self.gpu_timing = if enabled {
+            let frames_in_flight = self.frame_buffers.len();
+            Some(GpuTiming::new(&self.graphics_queue, frames_in_flight)?)
+        } else {
+            None
+        };
+        self.cached_command_buffer = None;
+        Ok(())
     }
 
-    fn image_descriptor_set(
-        &self,
-        image_view: Arc<dyn ImageViewAbstract + Send + Sync>,
-    ) -> Result<Arc<PersistentDescriptorSet>, DescriptorSetCreationError> {
-        let layout = self.pipeline.layout().descriptor_set_layouts()[0].clone();
-        let mut builder = PersistentDescriptorSet::start(layout);
-        builder
-            .add_sampled_image(image_view, self.sampler.clone())
-            .map_err(DescriptorSetCreationError::from)?;
-        let set = builder.build().map_err(DescriptorSetCreationError::from)?;
-        Ok(Arc::new(set))
+    /// Returns GPU time in nanoseconds of the last UI draw which results were read back.
+    ///
+    /// Results are read back without waiting when queries of the same frame are reset,
+    /// so they lag behind by count of frames in flight (or more if GPU is still busy).
+    /// Returns `None` if timing is disabled or no results were read back yet.
+    ///
+    pub fn last_gpu_time_ns(&self) -> Option<f64> {
+        self.gpu_timing.as_ref()?.last_time_ns()
     }
 
-    /// Registers new user texture to be drawn in UI.
-    pub fn register_texture(
+    /// Reads back GPU time of the previous draw of the frame with provided index
+    /// and builds a command buffer which resets its timestamp queries.
+    ///
+    /// Command buffer must be executed on the graphics queue
+    /// before the command buffer of the next [`draw`](UiDrawSystem::draw) of this frame.
+    /// Returns `None` if timing is disabled.
+    ///
+    pub fn reset_gpu_timing(
         &mut self,
-        image_view: Arc<dyn ImageViewAbstract + Send + Sync>,
-    ) -> Result<TextureId, DescriptorSetCreationError> {
-        let descriptor_set = self.image_descriptor_set(image_view)?;
-        let key = self.user_texture_descriptor_sets.insert(descriptor_set);
-        let id = key.data().as_ffi();
-        Ok(TextureId::User(id))
+        frame_index: usize,
+    ) -> Result<Option<PrimaryAutoCommandBuffer>, UiDrawError> {
+        match &mut self.gpu_timing {
+            Some(gpu_timing) => {
+                let command_buffer = gpu_timing.reset(&self.graphics_queue, frame_index)?;
+                Ok(Some(command_buffer))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Checks if texture of given format can be sampled in UI.
+    fn is_sampleable(&self, format: Format) -> bool {
+        let physical_device = self.graphics_queue.device().physical_device();
+        let format_support = FormatSupport::query(physical_device, format);
+        self::is_sampleable_format(format, format_support)
     }
 
     /// Unregisters previously registered user texture to be drawn in UI.
@@ -153,130 +1656,1159 @@ pub fn unregister_texture(&mut self, texture_id: TextureId) {
         if let TextureId::User(id) = texture_id {
             let key_data = KeyData::from_ffi(id);
             let key = DefaultKey::from(key_data);
-            self.user_texture_descriptor_sets.remove(key);
+            self.remove_texture(key);
         }
     }
 
+    /// Describes images of all registered user textures (useful for debugging texture bindings).
+    pub fn describe_textures(&self) -> Vec<(TextureId, ImageDescription)> {
+        self.user_textures
+            .iter()
+            .map(|(key, texture)| {
+                let id = key.data().as_ffi();
+                (TextureId::User(id), texture.description)
+            })
+            .collect()
+    }
+
     /// Builds a secondary command buffer that draws UI on the current subpass.
+    ///
+    /// Viewport size is in physical pixels; meshes are in logical points
+    /// which are converted into physical pixels with provided scale factor.
+    ///
+    /// Vertex and index data is allocated from buffers of the frame
+    /// with provided index (wrapped by count of frames in flight).
+    ///
+    /// If reuse of command buffer is enabled and UI was not changed since the last draw,
+    /// command buffer of the last draw is returned.
+    ///
+    /// If damage rectangles are provided (in the same units as meshes),
+    /// only damaged regions of the target are redrawn: meshes outside of all damage rectangles
+    /// are skipped and scissor of other meshes is limited by the damage rectangles they intersect.
+    /// Otherwise the whole target is redrawn.
+    ///
     pub fn draw(
         &mut self,
-        viewport_size: Size,
+        viewport_size: PhysicalSize,
         scale_factor: f32,
         meshes: Vec<ClippedMesh>,
         texture: Arc<Texture>,
-    ) -> Result<SecondaryAutoCommandBuffer, UiDrawError> {
-        use crate::graphics::shader::ui::vertex;
+        frame_index: usize,
+        damage: Option<&[Rect]>,
+    ) -> Result<Arc<SecondaryAutoCommandBuffer>, UiDrawError> {
+        let meshes = meshes
+            .into_iter()
+            .map(|mesh| (mesh, UiBlendMode::default()))
+            .collect();
+        self.draw_blended(
+            viewport_size,
+            scale_factor,
+            meshes,
+            texture,
+            frame_index,
+            damage,
+        )
+    }
+
+    /// Builds a secondary command buffer that draws UI on the current subpass,
+    /// blending each mesh with the target as tagged by the caller.
+    ///
+    /// Pipeline is rebound only when blend mode differs from the one of the previous mesh,
+    /// so consecutive meshes with the same blend mode are drawn without pipeline switches.
+    /// Meshes with MSDF textures are always drawn with alpha blending.
+    /// See [`draw`](UiDrawSystem::draw) for details.
+    ///
+    pub fn draw_blended(
+        &mut self,
+        viewport_size: PhysicalSize,
+        scale_factor: f32,
+        meshes: Vec<(ClippedMesh, UiBlendMode)>,
+        texture: Arc<Texture>,
+        frame_index: usize,
+        damage: Option<&[Rect]>,
+    ) -> Result<Arc<SecondaryAutoCommandBuffer>, UiDrawError> {
+        self.prepare(texture)?;
+        let reuse_command_buffer = self.reuse_command_buffer && self.gpu_timing.is_none();
+        let content_hash = reuse_command_buffer.then(|| {
+            self::content_hash(
+                &meshes,
+                damage,
+                viewport_size,
+                self.mesh_scale_factor(scale_factor),
+            )
+        });
+        if let (Some(content_hash), Some((cached_hash, command_buffer, stats))) =
+            (content_hash, &self.cached_command_buffer)
+        {
+            if content_hash == *cached_hash {
+                // Textures of reused command buffer are drawn again, so they are not evicted.
+                let draw_counter = self.next_draw_counter();
+                for (ClippedMesh(_, mesh), _) in &meshes {
+                    if let TextureId::User(id) = mesh.texture_id {
+                        Self::user_texture(&self.user_textures, draw_counter, id);
+                    }
+                }
+                self.stats = *stats;
+                return Ok(command_buffer.clone());
+            }
+        }
+
+        let usage = if reuse_command_buffer {
+            CommandBufferUsage::SimultaneousUse
+        } else {
+            CommandBufferUsage::OneTimeSubmit
+        };
+        let mut builder = AutoCommandBufferBuilder::secondary_graphics(
+            self.graphics_queue.device().clone(),
+            self.graphics_queue.family(),
+            usage,
+            self.pipeline.subpass().clone(),
+        )?;
+        let timed = match &mut self.gpu_timing {
+            Some(gpu_timing) => gpu_timing.write_begin(&mut builder, frame_index)?,
+            None => false,
+        };
+
+        let frame_buffers = &self.frame_buffers[frame_index % self.frame_buffers.len()];
+        self.stats = self.record_ui(
+            &mut builder,
+            frame_buffers,
+            viewport_size,
+            scale_factor,
+            meshes,
+            damage,
+        )?;
+
+        if let (true, Some(gpu_timing)) = (timed, &mut self.gpu_timing) {
+            gpu_timing.write_end(&mut builder, frame_index)?;
+        }
+        let command_buffer = Arc::new(builder.build()?);
+        self.cached_command_buffer =
+            content_hash.map(|hash| (hash, command_buffer.clone(), self.stats));
+        Ok(command_buffer)
+    }
+
+    /// Uploads `egui` base texture and builds descriptor sets of all textures,
+    /// so that UI could be recorded by [`record`](UiDrawSystem::record) from many threads.
+    ///
+    /// Must be called again after any user texture is registered or unregistered,
+    /// and whenever `egui` base texture is changed.
+    ///
+    pub fn prepare(&mut self, texture: Arc<Texture>) -> Result<(), UiDrawError> {
+        self.upload_texture(texture)?;
+        self.array_descriptor_sets()?;
+        Ok(())
+    }
 
+    /// Creates scratch state for recording of UI command buffers on one thread.
+    pub fn create_recorder(&self) -> UiRecorder {
+        let device = self.graphics_queue.device();
+        UiRecorder {
+            frame_buffers: self
+                .frame_buffers
+                .iter()
+                .map(|_| FrameBuffers::new(device))
+                .collect(),
+            stats: UiDrawStats::default(),
+        }
+    }
+
+    /// Builds a secondary command buffer that draws UI on the current subpass
+    /// using shared reference to the system, so many threads could record UI in parallel
+    /// (e.g. for viewports of different windows).
+    ///
+    /// Threading model: all state which is changed by drawing (buffer pools and statistics)
+    /// is owned by the recorder, so each thread should have its own recorder
+    /// created by [`create_recorder`](UiDrawSystem::create_recorder).
+    /// Textures are not uploaded by this method: all threads share `egui` base texture
+    /// and descriptor sets built by the last [`prepare`](UiDrawSystem::prepare) call,
+    /// which requires exclusive reference and must not race with recording.
+    ///
+    /// Command buffers are never reused and GPU time is not measured.
+    /// See [`draw_blended`](UiDrawSystem::draw_blended) for details.
+    ///
+    pub fn record(
+        &self,
+        recorder: &mut UiRecorder,
+        viewport_size: PhysicalSize,
+        scale_factor: f32,
+        meshes: Vec<(ClippedMesh, UiBlendMode)>,
+        frame_index: usize,
+        damage: Option<&[Rect]>,
+    ) -> Result<Arc<SecondaryAutoCommandBuffer>, UiDrawError> {
         let mut builder = AutoCommandBufferBuilder::secondary_graphics(
             self.graphics_queue.device().clone(),
             self.graphics_queue.family(),
             CommandBufferUsage::OneTimeSubmit,
             self.pipeline.subpass().clone(),
         )?;
+        let frame_buffers = &recorder.frame_buffers[frame_index % recorder.frame_buffers.len()];
+        recorder.stats = self.record_ui(
+            &mut builder,
+            frame_buffers,
+            viewport_size,
+            scale_factor,
+            meshes,
+            damage,
+        )?;
+        Ok(Arc::new(builder.build()?))
+    }
 
-        if texture.version != self.texture_version {
-            self.texture_version = texture.version;
-            let image = {
-                let dimensions = ImageDimensions::Dim2d {
-                    width: texture.width as u32,
-                    height: texture.height as u32,
-                    array_layers: 1,
-                };
-                let data: Vec<_> = texture.pixels.iter().flat_map(|&r| [r, r, r, r]).collect();
-
-                let (image, image_future) = ImmutableImage::from_iter(
-                    data.into_iter(),
-                    dimensions,
-                    MipmapsCount::One,
-                    Format::R8G8B8A8_UNORM,
-                    self.graphics_queue.clone(),
-                )?;
-                image_future.flush()?;
-                image
+    /// Records commands that draw UI directly into the primary command buffer.
+    ///
+    /// Command buffer must be inside of the subpass this system was created for,
+    /// which contents are recorded inline (not by secondary command buffers).
+    /// This saves allocation of secondary command buffer when UI is the only content
+    /// of the subpass, but commands are never reused and GPU time is not measured.
+    /// See [`draw`](UiDrawSystem::draw) for details.
+    ///
+    pub fn record_into(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        viewport_size: PhysicalSize,
+        scale_factor: f32,
+        meshes: Vec<ClippedMesh>,
+        texture: Arc<Texture>,
+        frame_index: usize,
+        damage: Option<&[Rect]>,
+    ) -> Result<(), UiDrawError> {
+        let meshes = meshes
+            .into_iter()
+            .map(|mesh| (mesh, UiBlendMode::default()))
+            .collect();
+        self.prepare(texture)?;
+        let frame_buffers = &self.frame_buffers[frame_index % self.frame_buffers.len()];
+        self.stats = self.record_ui(
+            builder,
+            frame_buffers,
+            viewport_size,
+            scale_factor,
+            meshes,
+            damage,
+        )?;
+        Ok(())
+    }
+
+    /// Records draw commands of meshes into command buffer of any level,
+    /// so that all draw paths tag meshes, apply the budget and count statistics the same way.
+    ///
+    /// Descriptor sets of texture arrays must be built by [`prepare`](UiDrawSystem::prepare).
+    ///
+    fn record_ui<B: UiCommandBuilder>(
+        &self,
+        builder: &mut B,
+        frame_buffers: &FrameBuffers,
+        viewport_size: PhysicalSize,
+        scale_factor: f32,
+        meshes: Vec<(ClippedMesh, UiBlendMode)>,
+        damage: Option<&[Rect]>,
+    ) -> Result<UiDrawStats, UiDrawError> {
+        let scale_factor = self.mesh_scale_factor(scale_factor);
+        let ((element_ids, blend_modes), meshes) = self::tag_meshes(meshes, damage);
+        let (meshes, dropped_meshes) = self::apply_budget(meshes, self.budget);
+        let array_descriptor_sets = self
+            .texture_arrays
+            .iter()
+            .map(TextureArray::built_descriptor_set)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(UiDrawError::NotPrepared)?;
+
+        let context = RecordContext {
+            frame_buffers,
+            array_descriptor_sets: &array_descriptor_sets,
+            draw_counter: self.next_draw_counter(),
+            viewport_size,
+            scale_factor,
+        };
+        let tagged_meshes = element_ids.into_iter().zip(blend_modes).zip(meshes);
+        let drawn_meshes = self.record_meshes(builder, context, tagged_meshes)?;
+        Ok(UiDrawStats {
+            dropped_meshes,
+            drawn_meshes,
+        })
+    }
+
+    /// Uploads `egui` base texture if it was changed since the last upload.
+    fn upload_texture(&mut self, texture: Arc<Texture>) -> Result<(), UiDrawError> {
+        if !self::is_texture_changed(self.uploaded_texture.as_ref(), &texture) {
+            return Ok(());
+        }
+        self.uploaded_texture = Some(texture.clone());
+        self.cached_command_buffer = None;
+        let image = {
+            let dimensions = ImageDimensions::Dim2d {
+                width: texture.width as u32,
+                height: texture.height as u32,
+                array_layers: 1,
             };
+            let data: Vec<_> = texture.pixels.iter().flat_map(|&r| [r, r, r, r]).collect();
 
-            let image = ImageView::new(image)?;
-            let set = self.image_descriptor_set(image)?;
-            self.texture_descriptor_set = Some(set);
+            let (image, image_future) = ImmutableImage::from_iter(
+                data.into_iter(),
+                dimensions,
+                MipmapsCount::One,
+                UI_TEXTURE_FORMAT,
+                self.graphics_queue.clone(),
+            )?;
+            image_future.flush()?;
+            image
+        };
+
+        let image = ImageView::new(image)?;
+        let sampler = self.samplers[&TextureOptions::default()].clone();
+        self.texture_arrays[0].set(EGUI_INDEX, (image, sampler));
+        Ok(())
+    }
+
+    /// Ratio between physical pixels and units of meshes drawn with given scale factor.
+    fn mesh_scale_factor(&self, scale_factor: f32) -> f32 {
+        if self.meshes_in_physical_pixels {
+            1.0
+        } else {
+            scale_factor
+        }
+    }
+
+    /// Increments number of the current draw call, returning the new number.
+    fn next_draw_counter(&self) -> u64 {
+        self.draw_counter.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Draws many copies of the base mesh with per-instance attributes by one draw call.
+    ///
+    /// This is an opt-in path for bulk UI elements identified by the caller (e.g. cells of
+    /// large grids or lists) which bypasses `egui` tessellation: base mesh (usually a quad)
+    /// is uploaded once, and each instance transforms its positions and UVs and multiplies
+    /// its color as described by [`UiInstance`]. Base mesh is clipped by its clip rectangle.
+    ///
+    /// `egui` base texture is the one uploaded by the last [`draw`](UiDrawSystem::draw)
+    /// or [`prepare`](UiDrawSystem::prepare) call. Meshes with MSDF textures cannot be drawn instanced.
+    /// Element id of instanced elements is zero, so they cannot be picked.
+    ///
+    /// Returned command buffer should be executed in the same subpass
+    /// after the command buffer returned by [`draw`](UiDrawSystem::draw).
+    ///
+    pub fn draw_instanced(
+        &mut self,
+        viewport_size: PhysicalSize,
+        scale_factor: f32,
+        base: ClippedMesh,
+        instances: Vec<UiInstance>,
+        frame_index: usize,
+    ) -> Result<Arc<SecondaryAutoCommandBuffer>, UiDrawError> {
+        use crate::graphics::shader::ui::vertex;
+
+        let scale_factor = self.mesh_scale_factor(scale_factor);
+        let mut builder = AutoCommandBufferBuilder::secondary_graphics(
+            self.graphics_queue.device().clone(),
+            self.graphics_queue.family(),
+            CommandBufferUsage::OneTimeSubmit,
+            self.instanced_pipeline.subpass().clone(),
+        )?;
+
+        let ClippedMesh(rect, mesh) = base;
+        // Nothing to draw if we don't have vertices, indices & instances
+        if mesh.vertices.is_empty() || mesh.indices.is_empty() || instances.is_empty() {
+            return Ok(Arc::new(builder.build()?));
         }
 
+        let array_descriptor_sets = self.array_descriptor_sets()?;
+        let binding = Self::texture_binding(
+            &self.user_textures,
+            self.next_draw_counter(),
+            mesh.texture_id,
+            &array_descriptor_sets,
+        );
+        let (descriptor_sets, texture_index, msdf, uv_transform) = match binding {
+            Some(binding) => binding,
+            None => return Ok(Arc::new(builder.build()?)),
+        };
+        if msdf.is_some() {
+            return Err(UiDrawError::MsdfNotInstanced);
+        }
+
+        let frame_buffers = &self.frame_buffers[frame_index % self.frame_buffers.len()];
         let width = viewport_size.width as f32;
         let height = viewport_size.height as f32;
+        let logical_size = viewport_size.to_logical(scale_factor);
+        let ui_size = self
+            .rotation
+            .ui_size(Vec2::new(logical_size.width, logical_size.height));
+        let push_constants = self::push_constants(
+            [ui_size.x, ui_size.y],
+            self.y_axis,
+            self.hdr_paper_white,
+            self.color_adjustment,
+            self.rotation,
+        );
         let push_constants = vertex::ty::PushConstants {
-            screen_size: [width / scale_factor, height / scale_factor],
+            texture_index,
+            ..push_constants
+        };
+        let viewport = Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [width, height],
+            depth_range: 0.0..1.0,
         };
+        let rect = self.rotation.rotate_rect(rect, ui_size);
+        let scissor = self::scissor(rect, scale_factor, viewport_size, self.y_axis);
 
-        for ClippedMesh(rect, mesh) in meshes {
-            // Nothing to draw if we don't have vertices & indices
-            if mesh.vertices.is_empty() || mesh.indices.is_empty() {
-                continue;
-            }
-            let scissor = {
-                let min = rect.min;
-                let min = Pos2 {
-                    x: min.x * scale_factor,
-                    y: min.y * scale_factor,
-                };
-                let min = Pos2 {
-                    x: min.x.clamp(0.0, width),
-                    y: min.y.clamp(0.0, height),
-                };
-                let max = rect.max;
-                let max = Pos2 {
-                    x: max.x * scale_factor,
-                    y: max.y * scale_factor,
-                };
-                let max = Pos2 {
-                    x: max.x.clamp(min.x, width),
-                    y: max.y.clamp(min.y, height),
-                };
-                Scissor {
-                    origin: [min.x.round() as u32, min.y.round() as u32],
-                    dimensions: [
-                        (max.x.round() - min.x) as u32,
-                        (max.y.round() - min.y) as u32,
-                    ],
-                }
-            };
+        let chunk = mesh
+            .vertices
+            .into_iter()
+            .map(|vertex| self::ui_vertex(vertex, uv_transform));
+        let vertex_buffer = frame_buffers.vertex_buffer.chunk(chunk)?;
+
+        let instance_count = instances.len() as u32;
+        let instance_buffer = frame_buffers.instance_buffer.chunk(instances)?;
+
+        let pipeline = &self.instanced_pipeline;
+        builder
+            .set_viewport(0, iter::once(viewport))
+            .set_scissor(0, iter::once(scissor))
+            .bind_pipeline_graphics(pipeline.clone())
+            .bind_vertex_buffers(0, (vertex_buffer, instance_buffer))
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                descriptor_sets,
+            )
+            .push_constants(pipeline.layout().clone(), 0, push_constants);
+        frame_buffers.draw_indexed(&mut builder, mesh.indices, instance_count, 0)?;
+
+        Ok(Arc::new(builder.build()?))
+    }
+
+    /// Records draw commands of meshes tagged with element ids and blend modes
+    /// into command buffer of any level, so that all draw paths share scissor,
+    /// viewport and texture handling.
+    ///
+    /// Returns count of meshes which were drawn.
+    ///
+    fn record_meshes<B, I>(
+        &self,
+        builder: &mut B,
+        context: RecordContext,
+        tagged_meshes: I,
+    ) -> Result<usize, UiDrawError>
+    where
+        B: UiCommandBuilder,
+        I: Iterator<Item = ((u32, UiBlendMode), ClippedMesh)>,
+    {
+        let RecordContext {
+            frame_buffers,
+            array_descriptor_sets,
+            draw_counter,
+            viewport_size,
+            scale_factor,
+        } = context;
+        let width = viewport_size.width as f32;
+        let height = viewport_size.height as f32;
+        let logical_size = viewport_size.to_logical(scale_factor);
+        let ui_size = self
+            .rotation
+            .ui_size(Vec2::new(logical_size.width, logical_size.height));
+        let push_constants = self::push_constants(
+            [ui_size.x, ui_size.y],
+            self.y_axis,
+            self.hdr_paper_white,
+            self.color_adjustment,
+            self.rotation,
+        );
+
+        // Viewport is the same for all meshes, so only scissor is set per mesh.
+        let viewport = Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [width, height],
+            depth_range: 0.0..1.0,
+        };
+        builder.set_viewport(viewport);
+
+        let mut tagged_meshes: Vec<_> = tagged_meshes.collect();
+        if let Some(text_outline) = &self.text_outline {
+            tagged_meshes = self::outline_text(tagged_meshes, text_outline);
+        }
+        if self.sort_by_texture {
+            self::group_by_descriptor_set(&mut tagged_meshes, |texture_id| {
+                let binding = Self::texture_binding(
+                    &self.user_textures,
+                    draw_counter,
+                    texture_id,
+                    array_descriptor_sets,
+                );
+                // Descriptor sets are compared by identity, as when they are bound.
+                binding.map_or(std::ptr::null(), |(descriptor_set, ..)| {
+                    Arc::as_ptr(&descriptor_set) as *const ()
+                })
+            });
+        }
+        // Meshes of unregistered textures are skipped, so they are never drawn with wrong texture.
+        tagged_meshes.retain(|(_, ClippedMesh(_, mesh))| {
+            let binding = Self::texture_binding(
+                &self.user_textures,
+                draw_counter,
+                mesh.texture_id,
+                array_descriptor_sets,
+            );
+            binding.is_some() && self::is_drawable(mesh, self.skip_transparent)
+        });
 
-            let chunk = mesh.vertices.into_iter().map(UiVertex::from);
-            let vertex_buffer = self.vertex_buffer.chunk(chunk)?;
+        // Vertices of all meshes are written into persistent buffer at once,
+        // so each mesh is drawn with offset of its first vertex in the buffer.
+        if self.persistent_vertex_buffers {
+            let vertices: Vec<_> = tagged_meshes
+                .iter()
+                .flat_map(|(_, ClippedMesh(_, mesh))| {
+                    let binding = Self::texture_binding(
+                        &self.user_textures,
+                        draw_counter,
+                        mesh.texture_id,
+                        array_descriptor_sets,
+                    );
+                    let uv_transform = binding.map_or(UvTransform::IDENTITY, |(.., uv)| uv);
+                    mesh.vertices
+                        .iter()
+                        .map(move |&vertex| self::ui_vertex(vertex, uv_transform))
+                })
+                .collect();
+            if !vertices.is_empty() {
+                let device = self.graphics_queue.device();
+                let vertex_buffer = frame_buffers.write_persistent(device, &vertices)?;
+                builder.builder().bind_vertex_buffers(0, vertex_buffer);
+            }
+        }
+        let mut next_vertex_offset = 0;
 
-            let chunk = mesh.indices.into_iter();
-            let index_buffer = self.index_buffer.chunk(chunk)?;
+        // Budget drops meshes from the end, so remaining meshes keep their element ids.
+        let mut bound_pipeline = None;
+        let mut bound_descriptor_sets = None;
+        let mut drawn_meshes = 0;
+        for ((element_id, blend_mode), ClippedMesh(rect, mesh)) in tagged_meshes {
+            let rect = self.rotation.rotate_rect(rect, ui_size);
+            let scissor = self::scissor(rect, scale_factor, viewport_size, self.y_axis);
 
-            let viewport = Viewport {
-                origin: [0.0, 0.0],
-                dimensions: [viewport_size.width as f32, viewport_size.height as f32],
-                depth_range: 0.0..1.0,
+            let binding = Self::texture_binding(
+                &self.user_textures,
+                draw_counter,
+                mesh.texture_id,
+                array_descriptor_sets,
+            );
+            let (descriptor_sets, texture_index, msdf, uv_transform) = match binding {
+                Some(binding) => binding,
+                None => continue,
             };
-            let descriptor_sets = match mesh.texture_id {
-                TextureId::Egui => self.texture_descriptor_set.as_ref().unwrap().clone(),
-                TextureId::User(id) => {
-                    let key_data = KeyData::from_ffi(id);
-                    let key = DefaultKey::from(key_data);
-                    self.user_texture_descriptor_sets
-                        .get(key)
-                        .expect("User texture was unregistered, but still in use!")
-                        .clone()
-                }
+
+            let vertex_offset = if self.persistent_vertex_buffers {
+                let vertex_offset = next_vertex_offset;
+                next_vertex_offset += mesh.vertices.len() as i32;
+                vertex_offset
+            } else {
+                let chunk = mesh
+                    .vertices
+                    .into_iter()
+                    .map(|vertex| self::ui_vertex(vertex, uv_transform));
+                let vertex_buffer = frame_buffers.vertex_buffer.chunk(chunk)?;
+                builder.builder().bind_vertex_buffers(0, vertex_buffer);
+                0
             };
-            builder
-                .set_viewport(0, std::iter::once(viewport))
-                .set_scissor(0, std::iter::once(scissor))
-                .bind_pipeline_graphics(self.pipeline.clone())
-                .bind_vertex_buffers(0, vertex_buffer)
-                .bind_index_buffer(index_buffer.clone())
-                .bind_descriptor_sets(
+
+            let pipeline_kind = PipelineKind::of_mesh(msdf.is_some(), blend_mode);
+            let pipeline = self.pipeline_of(pipeline_kind);
+            if bound_pipeline != Some(pipeline_kind) {
+                builder.builder().bind_pipeline_graphics(pipeline.clone());
+                bound_pipeline = Some(pipeline_kind);
+                bound_descriptor_sets = None;
+            }
+            let is_bound = bound_descriptor_sets
+                .as_ref()
+                .map_or(false, |bound| Arc::ptr_eq(bound, &descriptor_sets));
+            if !is_bound {
+                builder.builder().bind_descriptor_sets(
                     PipelineBindPoint::Graphics,
-                    self.pipeline.layout().clone(),
+                    pipeline.layout().clone(),
                     0,
-                    descriptor_sets,
-                )
-                .push_constants(self.pipeline.layout().clone(), 0, push_constants)
-                .draw_indexed(index_buffer.len() as u32, 1, 0, 0, 0)?;
+                    descriptor_sets.clone(),
+                );
+                bound_descriptor_sets = Some(descriptor_sets);
+            }
+            let push_constants =
+                self::mesh_push_constants(push_constants, texture_index, msdf, element_id);
+            builder.set_scissor(scissor);
+            builder
+                .builder()
+                .push_constants(pipeline.layout().clone(), 0, push_constants);
+            frame_buffers.draw_indexed(builder.builder(), mesh.indices, 1, vertex_offset)?;
+            drawn_meshes += 1;
+        }
+
+        Ok(drawn_meshes)
+    }
+
+    /// Retrieves graphics pipeline of given kind.
+    fn pipeline_of(&self, kind: PipelineKind) -> &Arc<GraphicsPipeline> {
+        match kind {
+            PipelineKind::Blend(UiBlendMode::Alpha) => &self.pipeline,
+            PipelineKind::Blend(UiBlendMode::Additive) => &self.additive_pipeline,
+            PipelineKind::Blend(UiBlendMode::Multiply) => &self.multiply_pipeline,
+            PipelineKind::Msdf => &self.msdf_pipeline,
+        }
+    }
+
+    /// Creates descriptor sets of all texture arrays.
+    fn array_descriptor_sets(
+        &mut self,
+    ) -> Result<Vec<Arc<dyn DescriptorSet + Send + Sync>>, DescriptorSetCreationError> {
+        let layout = self.pipeline.layout().descriptor_set_layouts()[0].clone();
+        self.texture_arrays
+            .iter_mut()
+            .map(|texture_array| texture_array.descriptor_set(layout.clone()))
+            .collect()
+    }
+
+    /// Retrieves descriptor set, index in the texture array, MSDF options
+    /// and UV transformation of the texture, marking it as drawn by given draw call.
+    ///
+    /// Returns `None` if the user texture was unregistered.
+    ///
+    fn texture_binding(
+        user_textures: &SlotMap<DefaultKey, UserTexture>,
+        draw_counter: u64,
+        texture_id: TextureId,
+        array_descriptor_sets: &[Arc<dyn DescriptorSet + Send + Sync>],
+    ) -> Option<(
+        Arc<dyn DescriptorSet + Send + Sync>,
+        u32,
+        Option<MsdfOptions>,
+        UvTransform,
+    )> {
+        let binding = match texture_id {
+            TextureId::Egui => (
+                array_descriptor_sets[0].clone(),
+                EGUI_INDEX,
+                None,
+                UvTransform::IDENTITY,
+            ),
+            TextureId::User(id) => {
+                let texture = Self::user_texture(user_textures, draw_counter, id)?;
+                let uv_transform = texture.uv_transform;
+                match &texture.binding {
+                    TextureBinding::DescriptorSet(set) => {
+                        (set.clone(), 0, texture.msdf, uv_transform)
+                    }
+                    TextureBinding::ArrayElement { array, index } => (
+                        array_descriptor_sets[*array].clone(),
+                        *index,
+                        None,
+                        uv_transform,
+                    ),
+                }
+            }
+        };
+        Some(binding)
+    }
+
+    /// Retrieves user texture by identifier packed into [`TextureId::User`],
+    /// marking it as drawn by given draw call.
+    ///
+    /// Returns `None` and logs a warning if the texture was unregistered
+    /// (e.g. evicted by capacity), but `egui` still uses it.
+    ///
+    fn user_texture(
+        user_textures: &SlotMap<DefaultKey, UserTexture>,
+        draw_counter: u64,
+        id: u64,
+    ) -> Option<&UserTexture> {
+        let key_data = KeyData::from_ffi(id);
+        let key = DefaultKey::from(key_data);
+        let texture = match user_textures.get(key) {
+            Some(texture) => texture,
+            None => {
+                log::warn!("user texture {} was unregistered, but still in use", id);
+                return None;
+            }
+        };
+        // Draws recorded in parallel could finish out of order.
+        texture
+            .last_drawn
+            .fetch_max(draw_counter, Ordering::Relaxed);
+        Some(texture)
+    }
+}
+
+/// Submits command buffer built by [`UiDrawSystem::draw`] to draw UI into the framebuffer.
+///
+/// Primary command buffer which begins the render pass of the framebuffer, executes UI commands
+/// and ends the render pass is executed after `wait` future, then semaphore is signaled
+/// and all the commands are flushed. Returned future can be used to present the image.
+///
+/// Render pass of the framebuffer must be compatible with the render pass
+/// of the subpass the draw system was created with, and must consist of that single subpass.
+/// Clear values are provided for each attachment of the framebuffer
+/// (`ClearValue::None` for attachments which are not cleared).
+///
+pub fn submit_ui<F>(
+    queue: Arc<Queue>,
+    command_buffer: Arc<SecondaryAutoCommandBuffer>,
+    wait: F,
+    framebuffer: Arc<dyn FramebufferAbstract + Send + Sync>,
+    clear_values: impl IntoIterator<Item = ClearValue>,
+) -> Result<Box<dyn GpuFuture + Send + Sync>, UiSubmitError>
+where
+    F: GpuFuture + Send + Sync + 'static,
+{
+    let mut builder = AutoCommandBufferBuilder::primary(
+        queue.device().clone(),
+        queue.family(),
+        CommandBufferUsage::OneTimeSubmit,
+    )?;
+    builder
+        .begin_render_pass(
+            framebuffer,
+            SubpassContents::SecondaryCommandBuffers,
+            clear_values,
+        )?
+        .execute_commands(command_buffer)?
+        .end_render_pass()?;
+    let primary_command_buffer = builder.build()?;
+
+    let future = wait
+        .then_execute(queue, primary_command_buffer)?
+        .then_signal_semaphore_and_flush()?;
+    Ok(Box::new(future))
+}
+
+/// Creates sRGB image view of UNORM color image to be used as UI render target.
+///
+/// UI colors are written through hardware sRGB conversion,
+/// while other passes can still use UNORM view of the same image.
+/// Image must be created with `mutable_format` flag (`VK_IMAGE_CREATE_MUTABLE_FORMAT_BIT`),
+/// otherwise creation of the view fails.
+///
+pub fn ui_srgb_view<I>(image: I) -> Result<Arc<ImageView<I>>, UiSrgbViewError>
+where
+    I: ImageAccess,
+{
+    let format = image.format();
+    let srgb_format = self::srgb_format(format).ok_or(UiSrgbViewError::NoSrgbFormat(format))?;
+    let image_view = ImageView::start(image).with_format(srgb_format).build()?;
+    Ok(image_view)
+}
+
+/// Retrieves sRGB counterpart of UNORM color format, if any.
+fn srgb_format(format: Format) -> Option<Format> {
+    let srgb_format = match format {
+        Format::R8_UNORM => Format::R8_SRGB,
+        Format::R8G8_UNORM => Format::R8G8_SRGB,
+        Format::R8G8B8_UNORM => Format::R8G8B8_SRGB,
+        Format::B8G8R8_UNORM => Format::B8G8R8_SRGB,
+        Format::R8G8B8A8_UNORM => Format::R8G8B8A8_SRGB,
+        Format::B8G8R8A8_UNORM => Format::B8G8R8A8_SRGB,
+        Format::A8B8G8R8_UNORM_PACK32 => Format::A8B8G8R8_SRGB_PACK32,
+        _ => return None,
+    };
+    Some(srgb_format)
+}
+
+/// Checks if texture of given format with given support by the device can be sampled in UI:
+/// only color formats which support sampling with optimal tiling are accepted.
+fn is_sampleable_format(format: Format, format_support: FormatSupport) -> bool {
+    format.aspects().color && format_support.sampled
+}
+
+/// Checks if all color attachments of the subpass support blending which is used to draw UI.
+fn check_blendable(
+    color_formats: &[Format],
+    format_support: impl Fn(Format) -> FormatSupport,
+) -> Result<(), UiDrawSystemCreationError> {
+    match color_formats
+        .iter()
+        .find(|&&format| !format_support(format).blendable)
+    {
+        Some(&format) => Err(UiDrawSystemCreationError::FormatNotBlendable(format)),
+        None => Ok(()),
+    }
+}
+
+/// Configures blending of each color attachment of the subpass with given formats.
+fn attachment_blends(
+    color_attachments: UiColorAttachments,
+    blend_mode: UiBlendMode,
+    color_formats: &[Format],
+    format_support: impl Fn(Format) -> FormatSupport,
+) -> Result<Vec<AttachmentBlend>, UiDrawSystemCreationError> {
+    let blend = blend_mode.attachment_blend();
+    match color_attachments {
+        UiColorAttachments::Color => {
+            self::check_blendable(color_formats, format_support)?;
+            Ok(vec![blend; color_formats.len()])
+        }
+        UiColorAttachments::ColorAndElementId => {
+            if color_formats.len() != 2 {
+                return Err(UiDrawSystemCreationError::ColorAttachmentCountMismatch {
+                    expected: 2,
+                    found: color_formats.len(),
+                });
+            }
+            self::check_blendable(&color_formats[..1], format_support)?;
+            Ok(vec![blend, AttachmentBlend::pass_through()])
+        }
+    }
+}
+
+/// Limits clip rectangle of the mesh by damage rectangles which intersect the mesh.
+///
+/// Returns `None` if the mesh does not intersect any damage rectangle.
+///
+fn damaged_clip_rect(clip_rect: Rect, mesh: &Mesh, damage: &[Rect]) -> Option<Rect> {
+    let bounds = mesh.vertices.iter().fold(Rect::NOTHING, |bounds, vertex| {
+        bounds.union(Rect::from_min_max(vertex.pos, vertex.pos))
+    });
+    let bounds = bounds.intersect(clip_rect);
+    let damaged = damage
+        .iter()
+        .filter(|damage| damage.intersects(bounds))
+        .fold(None, |damaged: Option<Rect>, &damage| {
+            Some(damaged.map_or(damage, |damaged| damaged.union(damage)))
+        })?;
+    Some(clip_rect.intersect(damaged))
+}
+
+/// Converts vertex of `egui` mesh into vertex of UI pipeline, transforming its texture coordinates.
+fn ui_vertex(mut vertex: Vertex, uv_transform: UvTransform) -> UiVertex {
+    vertex.uv = uv_transform.apply(vertex.uv);
+    UiVertex::from(vertex)
+}
+
+/// Capacity of the persistent vertex buffer which fits required count of vertices:
+/// current capacity if it is enough, otherwise the next power of two.
+fn persistent_capacity(capacity: usize, required: usize) -> usize {
+    if required <= capacity {
+        capacity
+    } else {
+        required.next_power_of_two()
+    }
+}
+
+/// Checks if persistent buffer could be overwritten in place.
+///
+/// Buffer must have required capacity and must not be referenced by anything but its frame:
+/// recorded command buffers keep their buffers alive until they are dropped,
+/// and GPU locks buffers only on submission, so write lock alone does not protect
+/// vertices of command buffers which were recorded but not submitted yet.
+///
+fn is_reusable<B: ?Sized>(buffer: &Arc<B>, len: usize, capacity: usize) -> bool {
+    len == capacity && Arc::strong_count(buffer) == 1
+}
+
+/// Creates sampler for each of [all texture sampling options](TextureOptions::ALL)
+/// by provided function, which takes filter, mipmap mode and address mode of the sampler.
+fn samplers<S, E>(
+    mut create_sampler: impl FnMut(Filter, MipmapMode, SamplerAddressMode) -> Result<S, E>,
+) -> Result<HashMap<TextureOptions, S>, E> {
+    TextureOptions::ALL
+        .iter()
+        .map(|&options| {
+            let (filter, mipmap_mode) = match options.filter {
+                TextureFilter::Nearest => (Filter::Nearest, MipmapMode::Nearest),
+                TextureFilter::Linear => (Filter::Linear, MipmapMode::Linear),
+            };
+            let address_mode = match options.wrap_mode {
+                TextureWrapMode::ClampToEdge => SamplerAddressMode::ClampToEdge,
+                TextureWrapMode::Repeat => SamplerAddressMode::Repeat,
+            };
+            let sampler = create_sampler(filter, mipmap_mode, address_mode)?;
+            Ok((options, sampler))
+        })
+        .collect()
+}
+
+/// Selects least recently drawn texture to be evicted if count of textures reached capacity.
+///
+/// Textures are given by their keys with numbers of the last draw calls they were drawn in.
+/// Textures drawn since the draw call `protected_since` are never selected.
+///
+fn eviction_candidate<K>(
+    textures: impl IntoIterator<Item = (K, u64)>,
+    count: usize,
+    capacity: Option<usize>,
+    protected_since: u64,
+) -> Option<K> {
+    if count < capacity?.max(1) {
+        return None;
+    }
+    let (key, _) = textures
+        .into_iter()
+        .filter(|&(_, last_drawn)| last_drawn < protected_since)
+        .min_by_key(|&(_, last_drawn)| last_drawn)?;
+    Some(key)
+}
+
+/// Checks if the mesh has anything to draw.
+fn is_drawable(mesh: &Mesh, skip_transparent: bool) -> bool {
+    // Nothing to draw if we don't have vertices & indices
+    if mesh.vertices.is_empty() || mesh.indices.is_empty() {
+        return false;
+    }
+    !(skip_transparent && self::is_fully_transparent(mesh))
+}
+
+/// Inserts shadow copies of each text mesh right before it,
+/// shifted by sample offsets and colored in the outline color.
+fn outline_text<T: Clone>(
+    tagged_meshes: Vec<(T, ClippedMesh)>,
+    text_outline: &TextOutline,
+) -> Vec<(T, ClippedMesh)> {
+    let mut outlined = Vec::with_capacity(tagged_meshes.len());
+    for (tag, ClippedMesh(rect, mesh)) in tagged_meshes {
+        if mesh.texture_id == TextureId::Egui {
+            for offset in text_outline.sample_offsets() {
+                let mut shadow = mesh.clone();
+                for vertex in &mut shadow.vertices {
+                    vertex.pos += offset;
+                    vertex.color = self::shadow_color(text_outline.color, vertex.color.a());
+                }
+                outlined.push((tag.clone(), ClippedMesh(rect, shadow)));
+            }
         }
+        outlined.push((tag, ClippedMesh(rect, mesh)));
+    }
+    outlined
+}
+
+/// Scales premultiplied shadow color by alpha of the text vertex.
+fn shadow_color(color: Color32, alpha: u8) -> Color32 {
+    let scale = |channel: u8| (channel as u32 * alpha as u32 / u8::MAX as u32) as u8;
+    Color32::from_rgba_premultiplied(
+        scale(color.r()),
+        scale(color.g()),
+        scale(color.b()),
+        scale(color.a()),
+    )
+}
+
+/// Groups meshes by descriptor sets of their textures in order of the first use of each set,
+/// preserving relative order of meshes within each group.
+///
+/// Descriptor set of the texture is identified by provided function,
+/// so textures of one texture array fall into the same group.
+///
+fn group_by_descriptor_set<T, K: Hash + Eq>(
+    tagged_meshes: &mut [(T, ClippedMesh)],
+    mut descriptor_set: impl FnMut(TextureId) -> K,
+) {
+    let mut groups = HashMap::new();
+    let mut texture_groups = HashMap::new();
+    for (_, ClippedMesh(_, mesh)) in tagged_meshes.iter() {
+        texture_groups.entry(mesh.texture_id).or_insert_with(|| {
+            let next_group = groups.len();
+            *groups
+                .entry(descriptor_set(mesh.texture_id))
+                .or_insert(next_group)
+        });
+    }
+    tagged_meshes.sort_by_key(|(_, ClippedMesh(_, mesh))| texture_groups[&mesh.texture_id]);
+}
+
+/// Assigns element ids to meshes (index of the mesh plus one) and limits their clip rectangles
+/// by damage rectangles, skipping meshes outside of all of them.
+///
+/// Returns element ids and blend modes of remaining meshes along with meshes themselves.
+///
+#[allow(clippy::type_complexity)]
+fn tag_meshes(
+    meshes: Vec<(ClippedMesh, UiBlendMode)>,
+    damage: Option<&[Rect]>,
+) -> ((Vec<u32>, Vec<UiBlendMode>), Vec<ClippedMesh>) {
+    // Element ids are assigned before any mesh is skipped.
+    meshes
+        .into_iter()
+        .zip(1..)
+        .filter_map(|((ClippedMesh(rect, mesh), blend_mode), element_id)| {
+            let rect = match damage {
+                Some(damage) => self::damaged_clip_rect(rect, &mesh, damage)?,
+                None => rect,
+            };
+            Some(((element_id, blend_mode), ClippedMesh(rect, mesh)))
+        })
+        .unzip()
+}
+
+/// Keeps the first meshes which fit into the budget.
+///
+/// Returns kept meshes and count of dropped ones.
+///
+fn apply_budget(mut meshes: Vec<ClippedMesh>, budget: UiDrawBudget) -> (Vec<ClippedMesh>, usize) {
+    let max_meshes = budget.max_meshes.unwrap_or(usize::MAX);
+    let max_vertices = budget.max_vertices.unwrap_or(usize::MAX);
+    let mut vertices = 0_usize;
+    let kept = meshes
+        .iter()
+        .take(max_meshes)
+        .take_while(|ClippedMesh(_, mesh)| {
+            vertices = vertices.saturating_add(mesh.vertices.len());
+            vertices <= max_vertices
+        })
+        .count();
+    let dropped = meshes.len() - kept;
+    meshes.truncate(kept);
+    (meshes, dropped)
+}
+
+/// Creates push constants which are shared by all meshes of the draw.
+fn push_constants(
+    screen_size: [f32; 2],
+    y_axis: YAxis,
+    hdr_paper_white: Option<f32>,
+    color_adjustment: ColorAdjustment,
+    rotation: UiRotation,
+) -> crate::graphics::shader::ui::vertex::ty::PushConstants {
+    use crate::graphics::shader::ui::vertex;
+
+    vertex::ty::PushConstants {
+        screen_size,
+        y_axis: y_axis.sign(),
+        texture_index: 0,
+        color_scale: hdr_paper_white.map_or(1.0, |paper_white| paper_white / SCRGB_WHITE_NITS),
+        msdf_threshold: 0.0,
+        msdf_smoothing: 0.0,
+        gamma: color_adjustment.gamma,
+        brightness: color_adjustment.brightness,
+        rotation: rotation.matrix(),
+        color_filter: color_adjustment.filter.matrix(),
+        element_id: 0,
+    }
+}
+
+/// Creates push constants of one mesh from push constants shared by all meshes.
+///
+/// Texture index selects the texture in the bound texture array
+/// (which is the only array if bindless textures are used).
+///
+fn mesh_push_constants(
+    push_constants: crate::graphics::shader::ui::vertex::ty::PushConstants,
+    texture_index: u32,
+    msdf: Option<MsdfOptions>,
+    element_id: u32,
+) -> crate::graphics::shader::ui::vertex::ty::PushConstants {
+    use crate::graphics::shader::ui::vertex;
+
+    let msdf = msdf.unwrap_or_default();
+    vertex::ty::PushConstants {
+        texture_index,
+        msdf_threshold: msdf.threshold,
+        msdf_smoothing: msdf.smoothing,
+        element_id,
+        ..push_constants
+    }
+}
+
+/// Converts clip rectangle of the mesh into scissor clamped by the viewport.
+///
+/// If Y axis of the target points up, UI is flipped vertically by vertex shader,
+/// so the scissor is mirrored vertically as well.
+///
+fn scissor(rect: Rect, scale_factor: f32, viewport_size: PhysicalSize, y_axis: YAxis) -> Scissor {
+    let width = viewport_size.width as f32;
+    let height = viewport_size.height as f32;
+    let (min_y, max_y) = match y_axis {
+        YAxis::Down => (rect.min.y * scale_factor, rect.max.y * scale_factor),
+        YAxis::Up => (
+            height - rect.max.y * scale_factor,
+            height - rect.min.y * scale_factor,
+        ),
+    };
+    let min = Pos2 {
+        x: rect.min.x * scale_factor,
+        y: min_y,
+    };
+    let min = Pos2 {
+        x: min.x.clamp(0.0, width),
+        y: min.y.clamp(0.0, height),
+    };
+    let max = Pos2 {
+        x: rect.max.x * scale_factor,
+        y: max_y,
+    };
+    let max = Pos2 {
+        x: max.x.clamp(min.x, width),
+        y: max.y.clamp(min.y, height),
+    };
+    // Both corners are rounded, so scissor never exceeds the viewport.
+    let origin = [min.x.round() as u32, min.y.round() as u32];
+    let max = [
+        (max.x.round() as u32).min(viewport_size.width),
+        (max.y.round() as u32).min(viewport_size.height),
+    ];
+    Scissor {
+        origin,
+        dimensions: [
+            max[0].saturating_sub(origin[0]),
+            max[1].saturating_sub(origin[1]),
+        ],
+    }
+}
 
-        Ok(builder.build()?)
+/// Calculates hash of UI content which affects commands of the draw.
+///
+/// Element ids of meshes are given by their order, so they are not hashed separately.
+///
+fn content_hash(
+    meshes: &[(ClippedMesh, UiBlendMode)],
+    damage: Option<&[Rect]>,
+    viewport_size: PhysicalSize,
+    scale_factor: f32,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let hash_rect = |rect: &Rect, hasher: &mut DefaultHasher| {
+        [rect.min.x, rect.min.y, rect.max.x, rect.max.y]
+            .iter()
+            .for_each(|value| value.to_bits().hash(hasher));
+    };
+    viewport_size.width.hash(&mut hasher);
+    viewport_size.height.hash(&mut hasher);
+    scale_factor.to_bits().hash(&mut hasher);
+    damage.map(<[Rect]>::len).hash(&mut hasher);
+    damage
+        .into_iter()
+        .flatten()
+        .for_each(|rect| hash_rect(rect, &mut hasher));
+    for (ClippedMesh(rect, mesh), blend_mode) in meshes {
+        hash_rect(rect, &mut hasher);
+        blend_mode.hash(&mut hasher);
+        mesh.texture_id.hash(&mut hasher);
+        mesh.indices.hash(&mut hasher);
+        for vertex in &mesh.vertices {
+            [vertex.pos.x, vertex.pos.y, vertex.uv.x, vertex.uv.y]
+                .iter()
+                .for_each(|value| value.to_bits().hash(&mut hasher));
+            vertex.color.hash(&mut hasher);
+        }
     }
+    hasher.finish()
+}
+
+/// Drops user textures, frees all elements of texture arrays (keeping only the first one)
+/// and forgets uploaded `egui` base texture, so that it is uploaded again by the next draw.
+fn reset_textures<V, T: Clone>(
+    user_textures: &mut SlotMap<DefaultKey, V>,
+    texture_arrays: &mut Vec<TextureArray<T>>,
+    uploaded_texture: &mut Option<Arc<Texture>>,
+) {
+    user_textures.clear();
+    texture_arrays.truncate(1);
+    texture_arrays[0].clear();
+    *uploaded_texture = None;
+}
+
+/// Checks if `egui` base texture differs from the uploaded one, either by identity or by version.
+fn is_texture_changed(uploaded: Option<&Arc<Texture>>, texture: &Arc<Texture>) -> bool {
+    uploaded.map_or(true, |uploaded| {
+        !Arc::ptr_eq(uploaded, texture) || uploaded.version != texture.version
+    })
+}
+
+/// Checks if all vertices of the mesh are fully transparent.
+fn is_fully_transparent(mesh: &Mesh) -> bool {
+    mesh.vertices.iter().all(|vertex| vertex.color.a() == 0)
 }
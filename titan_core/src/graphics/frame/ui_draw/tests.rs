@@ -0,0 +1,1275 @@
+#![cfg(test)]
+
+use egui::{Color32, Pos2};
+use epaint::{Mesh, Vertex};
+use vulkano::device::physical::PhysicalDevice;
+use vulkano::device::{DeviceExtensions, Features};
+use vulkano::image::AttachmentImage;
+use vulkano::instance::{Instance, InstanceExtensions};
+use vulkano::render_pass::{Framebuffer, RenderPass};
+
+use super::*;
+
+fn mesh(colors: &[Color32]) -> Mesh {
+    let mut mesh = Mesh::default();
+    mesh.vertices = colors
+        .iter()
+        .map(|&color| Vertex {
+            pos: Pos2::ZERO,
+            uv: Pos2::ZERO,
+            color,
+        })
+        .collect();
+    mesh.indices = (0..colors.len() as u32).collect();
+    mesh
+}
+
+#[test]
+fn test_fully_transparent() {
+    let transparent = mesh(&[Color32::TRANSPARENT; 3]);
+    assert!(is_fully_transparent(&transparent));
+
+    let visible = mesh(&[Color32::TRANSPARENT, Color32::WHITE, Color32::TRANSPARENT]);
+    assert!(!is_fully_transparent(&visible));
+}
+
+#[test]
+fn test_scissor() {
+    let rect = Rect::from_min_max(Pos2::new(10.0, 20.0), Pos2::new(110.0, 70.0));
+    let viewport_size = PhysicalSize::new(800, 600);
+
+    let logical = scissor(rect, 2.0, viewport_size, YAxis::Down);
+    assert_eq!(logical.origin, [20, 40]);
+    assert_eq!(logical.dimensions, [200, 100]);
+
+    // Meshes in physical pixels are drawn with unit scale factor.
+    let physical = scissor(rect, 1.0, viewport_size, YAxis::Down);
+    assert_eq!(physical.origin, [10, 20]);
+    assert_eq!(physical.dimensions, [100, 50]);
+}
+
+#[test]
+fn test_scissor_y_axis() {
+    // Clip rectangle in the top left quarter, away from the vertical center.
+    let rect = Rect::from_min_max(Pos2::new(10.0, 20.0), Pos2::new(110.0, 70.0));
+    let viewport_size = PhysicalSize::new(800, 600);
+
+    let down = scissor(rect, 1.0, viewport_size, YAxis::Down);
+    assert_eq!(down.origin, [10, 20]);
+    assert_eq!(down.dimensions, [100, 50]);
+
+    // Geometry is flipped by vertex shader, so the scissor is mirrored at the bottom.
+    let up = scissor(rect, 1.0, viewport_size, YAxis::Up);
+    assert_eq!(up.origin, [10, 530]);
+    assert_eq!(up.dimensions, [100, 50]);
+
+    let up = scissor(rect, 2.0, viewport_size, YAxis::Up);
+    assert_eq!(up.origin, [20, 460]);
+    assert_eq!(up.dimensions, [200, 100]);
+}
+
+#[test]
+fn test_scissor_in_bounds() {
+    let viewport_size = PhysicalSize::new(1366, 768);
+    let scale_factor = 1.25;
+    for step in 0..100 {
+        let offset = step as f32 * 0.1;
+        let rect = Rect::from_min_max(Pos2::new(offset, offset), Pos2::new(1092.5, 614.5));
+        let scissor = scissor(rect, scale_factor, viewport_size, YAxis::Down);
+        assert!(scissor.origin[0] + scissor.dimensions[0] <= viewport_size.width);
+        assert!(scissor.origin[1] + scissor.dimensions[1] <= viewport_size.height);
+    }
+}
+
+#[test]
+fn test_damaged_clip_rect() {
+    let mut mesh = mesh(&[Color32::WHITE; 3]);
+    let positions = [
+        Pos2::new(10.0, 10.0),
+        Pos2::new(50.0, 10.0),
+        Pos2::new(10.0, 50.0),
+    ];
+    for (vertex, pos) in mesh.vertices.iter_mut().zip(positions) {
+        vertex.pos = pos;
+    }
+    let clip_rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(100.0, 100.0));
+
+    let outside = [Rect::from_min_max(
+        Pos2::new(60.0, 60.0),
+        Pos2::new(90.0, 90.0),
+    )];
+    assert_eq!(damaged_clip_rect(clip_rect, &mesh, &outside), None);
+
+    let inside = Rect::from_min_max(Pos2::new(20.0, 0.0), Pos2::new(40.0, 30.0));
+    let damage = [inside, outside[0]];
+    assert_eq!(damaged_clip_rect(clip_rect, &mesh, &damage), Some(inside));
+}
+
+#[test]
+fn test_check_blendable() {
+    let format_support = |format| FormatSupport {
+        blendable: format != Format::R32G32B32A32_SINT,
+        ..FormatSupport::default()
+    };
+
+    let blendable = [Format::B8G8R8A8_SRGB, Format::R16G16B16A16_SFLOAT];
+    assert!(check_blendable(&blendable, format_support).is_ok());
+
+    let non_blendable = [Format::B8G8R8A8_SRGB, Format::R32G32B32A32_SINT];
+    assert!(matches!(
+        check_blendable(&non_blendable, format_support),
+        Err(UiDrawSystemCreationError::FormatNotBlendable(
+            Format::R32G32B32A32_SINT
+        )),
+    ));
+}
+
+#[test]
+fn test_sampleable_format() {
+    let sampled = FormatSupport {
+        sampled: true,
+        ..FormatSupport::default()
+    };
+    assert!(is_sampleable_format(Format::R8G8B8A8_SRGB, sampled));
+    assert!(is_sampleable_format(Format::BC1_RGBA_SRGB_BLOCK, sampled));
+
+    // Depth textures are rejected even if the device can sample them.
+    assert!(!is_sampleable_format(Format::D32_SFLOAT, sampled));
+    assert!(!is_sampleable_format(Format::D24_UNORM_S8_UINT, sampled));
+
+    let unsampled = FormatSupport::default();
+    assert!(!is_sampleable_format(Format::R8G8B8A8_SRGB, unsampled));
+}
+
+#[test]
+fn test_attachment_blends() {
+    let format_support = |format| FormatSupport {
+        blendable: format != Format::R32_UINT,
+        ..FormatSupport::default()
+    };
+    let formats = [Format::B8G8R8A8_SRGB, Format::R32_UINT];
+
+    // Element id attachment is not blended.
+    let blends = attachment_blends(
+        UiColorAttachments::ColorAndElementId,
+        UiBlendMode::Alpha,
+        &formats,
+        format_support,
+    )
+    .unwrap();
+    assert_eq!(blends.len(), 2);
+    assert_eq!(blends[1], AttachmentBlend::pass_through());
+    assert_ne!(blends[0], AttachmentBlend::pass_through());
+
+    assert!(matches!(
+        attachment_blends(
+            UiColorAttachments::Color,
+            UiBlendMode::Alpha,
+            &formats,
+            format_support
+        ),
+        Err(UiDrawSystemCreationError::FormatNotBlendable(
+            Format::R32_UINT
+        )),
+    ));
+    assert!(matches!(
+        attachment_blends(
+            UiColorAttachments::ColorAndElementId,
+            UiBlendMode::Alpha,
+            &formats[..1],
+            format_support,
+        ),
+        Err(UiDrawSystemCreationError::ColorAttachmentCountMismatch {
+            expected: 2,
+            found: 1,
+        }),
+    ));
+}
+
+#[test]
+fn test_blend_mode_pipeline() {
+    // Additive-tagged mesh binds the additive pipeline, unless its texture is MSDF.
+    assert_eq!(
+        PipelineKind::of_mesh(false, UiBlendMode::Additive),
+        PipelineKind::Blend(UiBlendMode::Additive),
+    );
+    assert_eq!(
+        PipelineKind::of_mesh(true, UiBlendMode::Additive),
+        PipelineKind::Msdf,
+    );
+    assert_eq!(UiBlendMode::default(), UiBlendMode::Alpha);
+
+    let format_support = |_| FormatSupport {
+        blendable: true,
+        ..FormatSupport::default()
+    };
+    let formats = [Format::B8G8R8A8_SRGB];
+    let blends = |blend_mode| {
+        attachment_blends(
+            UiColorAttachments::Color,
+            blend_mode,
+            &formats,
+            format_support,
+        )
+        .unwrap()[0]
+    };
+    let additive = blends(UiBlendMode::Additive);
+    assert_eq!(additive.color_source, BlendFactor::One);
+    assert_eq!(additive.color_destination, BlendFactor::One);
+    let multiply = blends(UiBlendMode::Multiply);
+    assert_eq!(multiply.color_source, BlendFactor::DstColor);
+    assert_eq!(multiply.color_destination, BlendFactor::OneMinusSrcAlpha);
+}
+
+#[test]
+fn test_texture_array_group() {
+    let reserved = EGUI_INDEX as usize + 1;
+    let mut texture_array = TextureArray::new(0_u32, TEXTURE_GROUP_LEN, reserved);
+    assert!(texture_array.is_empty());
+
+    // Text atlas and icon atlas are bound with one descriptor set.
+    let text = texture_array.insert(1).unwrap();
+    let icons = texture_array.insert(2).unwrap();
+    assert_eq!((text, icons), (1, 2));
+    assert!(!texture_array.is_empty());
+
+    assert_eq!(texture_array.insert(3), Some(3));
+    assert_eq!(texture_array.insert(4), None);
+
+    texture_array.remove(icons);
+    assert_eq!(texture_array.insert(5), Some(icons));
+}
+
+#[test]
+fn test_apply_budget() {
+    let meshes = || {
+        [3, 3, 3]
+            .iter()
+            .map(|&len| ClippedMesh(Rect::EVERYTHING, mesh(&vec![Color32::WHITE; len])))
+            .collect::<Vec<_>>()
+    };
+
+    let (kept, dropped) = apply_budget(meshes(), UiDrawBudget::default());
+    assert_eq!((kept.len(), dropped), (3, 0));
+
+    let budget = UiDrawBudget {
+        max_meshes: Some(2),
+        ..UiDrawBudget::default()
+    };
+    let (kept, dropped) = apply_budget(meshes(), budget);
+    assert_eq!((kept.len(), dropped), (2, 1));
+
+    // Mesh which exceeds the budget is dropped along with all the following ones.
+    let budget = UiDrawBudget {
+        max_vertices: Some(5),
+        ..UiDrawBudget::default()
+    };
+    let (kept, dropped) = apply_budget(meshes(), budget);
+    assert_eq!((kept.len(), dropped), (1, 2));
+}
+
+#[test]
+fn test_texture_changed() {
+    let texture = || {
+        Arc::new(Texture {
+            version: 0,
+            width: 1,
+            height: 1,
+            pixels: vec![u8::MAX],
+        })
+    };
+    let first = texture();
+    assert!(is_texture_changed(None, &first));
+    assert!(!is_texture_changed(Some(&first), &first.clone()));
+
+    // Other texture of the same version must be uploaded too.
+    let second = texture();
+    assert!(is_texture_changed(Some(&first), &second));
+}
+
+#[test]
+fn test_uv_transform() {
+    let uv = Pos2::new(0.5, 1.0);
+    assert_eq!(UvTransform::default().apply(uv), uv);
+
+    // Texture occupies the right bottom quarter of the atlas.
+    let atlased = UvTransform {
+        scale: [0.5, 0.5],
+        offset: [0.5, 0.5],
+    };
+    assert_eq!(atlased.apply(Pos2::ZERO), Pos2::new(0.5, 0.5));
+    assert_eq!(atlased.apply(uv), Pos2::new(0.75, 1.0));
+}
+
+#[test]
+fn test_push_constants() {
+    let constants = push_constants(
+        [800.0, 600.0],
+        YAxis::Up,
+        None,
+        Default::default(),
+        Default::default(),
+    );
+    assert_eq!(constants.screen_size, [800.0, 600.0]);
+    assert_eq!(constants.y_axis, -1.0);
+    assert_eq!(constants.color_scale, 1.0);
+    assert_eq!((constants.gamma, constants.brightness), (1.0, 1.0));
+    assert_eq!(constants.color_filter, ColorFilter::IDENTITY);
+
+    let color_adjustment = ColorAdjustment {
+        filter: ColorFilter::Deuteranopia,
+        gamma: 2.2,
+        brightness: 1.5,
+    };
+    let constants = push_constants(
+        [800.0, 600.0],
+        YAxis::Down,
+        Some(160.0),
+        color_adjustment,
+        UiRotation::Rotate90,
+    );
+    assert_eq!(constants.color_scale, 2.0);
+    assert_eq!((constants.gamma, constants.brightness), (2.2, 1.5));
+    assert_eq!(constants.rotation, UiRotation::Rotate90.matrix());
+    assert_eq!(constants.color_filter, ColorFilter::DEUTERANOPIA);
+}
+
+/// Adjusts premultiplied color the same way as the default UI fragment shaders.
+fn adjust_color(
+    color: [f32; 4],
+    constants: &crate::graphics::shader::ui::vertex::ty::PushConstants,
+) -> [f32; 4] {
+    let alpha = color[3];
+    let adjust = |premultiplied: f32| {
+        let straight = if alpha > 0.0 {
+            premultiplied / alpha
+        } else {
+            0.0
+        };
+        let straight = straight.powf(constants.gamma) * constants.brightness;
+        straight * alpha * constants.color_scale
+    };
+    [adjust(color[0]), adjust(color[1]), adjust(color[2]), alpha]
+}
+
+#[test]
+fn test_color_adjustment_of_translucent_color() {
+    let color_adjustment = ColorAdjustment {
+        filter: ColorFilter::None,
+        gamma: 2.0,
+        brightness: 1.0,
+    };
+    let constants = push_constants(
+        [800.0, 600.0],
+        YAxis::Down,
+        Some(160.0),
+        color_adjustment,
+        UiRotation::default(),
+    );
+
+    // Half-transparent gray is adjusted as straight color, then premultiplied again:
+    // gamma of premultiplied color would give 0.0625 instead of 0.125.
+    let [r, g, b, a] = adjust_color([0.25, 0.25, 0.25, 0.5], &constants);
+    assert_eq!(a, 0.5);
+    // Paper white scale is applied after gamma, so it is not raised to the power.
+    assert_eq!([r, g, b], [0.25; 3]);
+
+    // Fully transparent color stays black instead of dividing by zero.
+    assert_eq!(adjust_color([0.0; 4], &constants), [0.0; 4]);
+}
+
+#[test]
+fn test_descriptor_set_cache() {
+    let mut cache = DescriptorSetCache::<u32>::default();
+    let mut builds = 0;
+    let mut build = || {
+        builds += 1;
+        Ok::<_, ()>(builds)
+    };
+
+    // The same image view registered twice reuses its descriptor set.
+    assert_eq!(cache.get_or_try_insert_with((1, 2), &mut build), Ok(1));
+    assert_eq!(cache.get_or_try_insert_with((1, 2), &mut build), Ok(1));
+    assert_eq!(cache.get_or_try_insert_with((3, 2), &mut build), Ok(2));
+
+    cache.clear();
+    assert_eq!(cache.get_or_try_insert_with((1, 2), &mut build), Ok(3));
+}
+
+#[test]
+fn test_srgb_format() {
+    assert_eq!(
+        srgb_format(Format::B8G8R8A8_UNORM),
+        Some(Format::B8G8R8A8_SRGB),
+    );
+    assert_eq!(
+        srgb_format(Format::R8G8B8A8_UNORM),
+        Some(Format::R8G8B8A8_SRGB),
+    );
+    assert_eq!(srgb_format(Format::R16G16B16A16_SFLOAT), None);
+}
+
+#[test]
+fn test_rotation() {
+    let rotation = UiRotation::Rotate90;
+    // Right edge of UI is moved to the bottom of the target.
+    assert_eq!(
+        rotation.rotate_ndc(Vec2::new(1.0, 0.0)),
+        Vec2::new(0.0, 1.0)
+    );
+    assert_eq!(
+        rotation.rotate_ndc(Vec2::new(0.0, 1.0)),
+        Vec2::new(-1.0, 0.0)
+    );
+
+    // Portrait UI is drawn into landscape target.
+    let ui_size = rotation.ui_size(Vec2::new(800.0, 600.0));
+    assert_eq!(ui_size, Vec2::new(600.0, 800.0));
+
+    // Top left quarter of UI is the top right quarter of the target.
+    let rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(300.0, 400.0));
+    let rotated = rotation.rotate_rect(rect, ui_size);
+    assert_eq!(
+        rotated,
+        Rect::from_min_max(Pos2::new(400.0, 0.0), Pos2::new(800.0, 300.0))
+    );
+
+    let identity = UiRotation::default();
+    assert_eq!(identity.rotate_rect(rect, ui_size), rect);
+
+    let transform = vulkano::swapchain::SurfaceTransform::Rotate270;
+    assert_eq!(UiRotation::from(transform), UiRotation::Rotate270);
+}
+
+#[test]
+fn test_instance_layout() {
+    use ultraviolet::Vec2;
+    use vulkano::pipeline::vertex::Vertex as _;
+
+    let members = [
+        ("offset", 0),
+        ("scale", 8),
+        ("uv_offset", 16),
+        ("uv_scale", 24),
+        ("instance_color", 32),
+    ];
+    for (name, offset) in members {
+        let info = UiInstance::member(name).unwrap();
+        assert_eq!(info.offset, offset, "offset of `{}` member", name);
+    }
+
+    // Grid of 1000 cells is tightly packed into one instance buffer.
+    let instances: Vec<_> = (0..1000)
+        .map(|i| {
+            let offset = Vec2::new((i % 40) as f32 * 20.0, (i / 40) as f32 * 20.0);
+            UiInstance::new(offset, Vec2::broadcast(16.0), Default::default())
+        })
+        .collect();
+    assert_eq!(std::mem::size_of_val(instances.as_slice()), 1000 * 48);
+    assert_eq!(*instances[999].offset, Vec2::new(780.0, 480.0));
+    assert_eq!(*instances[999].uv_scale, Vec2::one());
+}
+
+#[test]
+#[ignore = "requires Vulkan device"]
+fn test_draw_instanced() {
+    use palette::Srgba;
+    use ultraviolet::Vec2;
+    use vulkano::image::ImageUsage;
+
+    let graphics_queue = graphics_queue();
+    let device = graphics_queue.device().clone();
+    let render_pass = ui_render_pass(&graphics_queue);
+    let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+    let mut system = UiDrawSystem::new(graphics_queue.clone(), subpass, 1).unwrap();
+    let texture = Arc::new(Texture {
+        version: 0,
+        width: 1,
+        height: 1,
+        pixels: vec![u8::MAX],
+    });
+    system.prepare(texture).unwrap();
+
+    // Unit quad is scaled by instances into cells of the grid with gaps between them.
+    let mut quad = mesh(&[Color32::WHITE; 4]);
+    let corners = [
+        Pos2::new(0.0, 0.0),
+        Pos2::new(1.0, 0.0),
+        Pos2::new(1.0, 1.0),
+        Pos2::new(0.0, 1.0),
+    ];
+    for (vertex, pos) in quad.vertices.iter_mut().zip(corners) {
+        vertex.pos = pos;
+    }
+    quad.indices = vec![0, 1, 2, 0, 2, 3];
+    let white = Srgba::new(1.0, 1.0, 1.0, 1.0);
+    let instances: Vec<_> = (0..1000)
+        .map(|i| {
+            let offset = Vec2::new((i % 40) as f32 * 20.0, (i / 40) as f32 * 20.0);
+            UiInstance::new(offset, Vec2::broadcast(16.0), white)
+        })
+        .collect();
+    let (width, height) = (800, 600);
+    let clip_rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(width as f32, height as f32));
+    let command_buffer = system
+        .draw_instanced(
+            PhysicalSize::new(width, height),
+            1.0,
+            ClippedMesh(clip_rect, quad),
+            instances,
+            0,
+        )
+        .unwrap();
+
+    let image = AttachmentImage::with_usage(
+        device.clone(),
+        [width, height],
+        Format::B8G8R8A8_SRGB,
+        ImageUsage {
+            color_attachment: true,
+            transfer_source: true,
+            ..ImageUsage::none()
+        },
+    )
+    .unwrap();
+    let framebuffer = Framebuffer::start(render_pass)
+        .add(ImageView::new(image.clone()).unwrap())
+        .unwrap()
+        .build()
+        .unwrap();
+    let pixel_count = width as usize * height as usize;
+    let buffer = CpuAccessibleBuffer::from_iter(
+        device.clone(),
+        BufferUsage::transfer_destination(),
+        false,
+        (0..pixel_count * 4).map(|_| 0_u8),
+    )
+    .unwrap();
+    let mut builder = AutoCommandBufferBuilder::primary(
+        device.clone(),
+        graphics_queue.family(),
+        CommandBufferUsage::OneTimeSubmit,
+    )
+    .unwrap();
+    builder
+        .begin_render_pass(
+            Arc::new(framebuffer),
+            SubpassContents::SecondaryCommandBuffers,
+            [ClearValue::Float([0.0, 0.0, 0.0, 0.0])],
+        )
+        .unwrap()
+        .execute_commands(command_buffer)
+        .unwrap()
+        .end_render_pass()
+        .unwrap()
+        .copy_image_to_buffer(image, buffer.clone())
+        .unwrap();
+    vulkano::sync::now(device)
+        .then_execute(graphics_queue, builder.build().unwrap())
+        .unwrap()
+        .then_signal_fence_and_flush()
+        .unwrap()
+        .wait(None)
+        .unwrap();
+
+    let pixels = buffer.read().unwrap();
+    let alpha = |x: usize, y: usize| pixels[(y * width as usize + x) * 4 + 3];
+    // Centers of the first and the last instances are drawn.
+    assert_eq!(alpha(8, 8), u8::MAX);
+    assert_eq!(alpha(788, 488), u8::MAX);
+    // Gap between cells and the area below the grid are not.
+    assert_eq!(alpha(18, 8), 0);
+    assert_eq!(alpha(400, 550), 0);
+}
+
+#[test]
+fn test_mesh_indices() {
+    let small = mesh(&[Color32::WHITE; 3]);
+    assert_eq!(
+        MeshIndices::new(small.indices),
+        MeshIndices::U16(vec![0, 1, 2]),
+    );
+
+    let max = u16::MAX as u32;
+    assert_eq!(
+        MeshIndices::new(vec![0, max]),
+        MeshIndices::U16(vec![0, u16::MAX]),
+    );
+    assert_eq!(
+        MeshIndices::new(vec![0, max + 1]),
+        MeshIndices::U32(vec![0, max + 1]),
+    );
+}
+
+/// Fragment shader with scanlines effect which is compatible with the default UI shaders.
+mod scanlines {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        include: ["src/graphics/shader"],
+        src: "
+            #version 450
+
+            layout(location = 0) in vec4 color;
+            layout(location = 1) in vec2 uv;
+
+            layout(location = 0) out vec4 outColor;
+
+            #include <ui_push_constants.glsl>
+
+            layout(binding = 0, set = 0) uniform sampler2D textures[4];
+
+            void main() {
+                outColor = color * texture(textures[0], uv);
+                float scanline = 0.75 + 0.25 * step(0.5, fract(gl_FragCoord.y * 0.5));
+                outColor.rgb *= scanline * pushConstants.brightness;
+            }
+        ",
+    }
+}
+
+#[test]
+fn test_custom_fragment_shader_interface() {
+    use crate::graphics::shader::ui::{fragment, vertex};
+
+    let size = std::mem::size_of::<vertex::ty::PushConstants>();
+    assert_eq!(size, std::mem::size_of::<fragment::ty::PushConstants>());
+    assert_eq!(size, std::mem::size_of::<scanlines::ty::PushConstants>());
+}
+
+#[test]
+fn test_gpu_timing_elapsed() {
+    use gpu_timing::elapsed_ns;
+
+    assert_eq!(elapsed_ns(100, 350, 64, 1.0), 250.0);
+    assert_eq!(elapsed_ns(100, 350, 64, 2.5), 625.0);
+    // Bits above valid ones are ignored, and counter could wrap around.
+    assert_eq!(elapsed_ns(0xFF00_0000_0010, 0x20, 32, 1.0), 16.0);
+    assert_eq!(elapsed_ns(0xFFFF_FFF0, 0x10, 32, 1.0), 32.0);
+}
+
+#[test]
+fn test_parallel_recording() {
+    fn assert_sync<T: Sync>() {}
+    fn assert_send<T: Send>() {}
+
+    // Threads record UI with shared system and their own recorders.
+    assert_sync::<UiDrawSystem>();
+    assert_send::<UiDrawSystem>();
+    assert_send::<UiRecorder>();
+    assert_send::<Arc<SecondaryAutoCommandBuffer>>();
+}
+
+/// Creates queue of the first physical device which supports graphics.
+fn graphics_queue() -> Arc<Queue> {
+    let instance = Instance::new(
+        None,
+        vulkano::Version::V1_1,
+        &InstanceExtensions::none(),
+        None,
+    )
+    .unwrap();
+    let physical_device = PhysicalDevice::enumerate(&instance)
+        .find(|device| {
+            device
+                .queue_families()
+                .any(|family| family.supports_graphics())
+        })
+        .expect("no physical device with graphics queue");
+    let family = physical_device
+        .queue_families()
+        .find(|family| family.supports_graphics())
+        .unwrap();
+    let (_, mut queues) = Device::new(
+        physical_device,
+        &Features::none(),
+        &DeviceExtensions::none(),
+        [(family, 1.0)],
+    )
+    .unwrap();
+    queues.next().unwrap()
+}
+
+/// Creates render pass with one color attachment which UI is drawn into.
+fn ui_render_pass(graphics_queue: &Arc<Queue>) -> Arc<RenderPass> {
+    let render_pass = vulkano::single_pass_renderpass!(
+        graphics_queue.device().clone(),
+        attachments: {
+            color: {
+                load: Clear,
+                store: Store,
+                format: Format::B8G8R8A8_SRGB,
+                samples: 1,
+            }
+        },
+        pass: {
+            color: [color],
+            depth_stencil: {}
+        }
+    );
+    Arc::new(render_pass.unwrap())
+}
+
+#[test]
+#[ignore = "requires Vulkan device"]
+fn test_color_attachment_layouts() {
+    let graphics_queue = graphics_queue();
+
+    // Default shaders declare no element id output, so they fit one color attachment.
+    let render_pass = ui_render_pass(&graphics_queue);
+    let subpass = Subpass::from(render_pass, 0).unwrap();
+    UiDrawSystem::new(graphics_queue.clone(), subpass, 1).unwrap();
+
+    // Element id shaders are used only if the subpass has element id attachment.
+    let render_pass = vulkano::single_pass_renderpass!(
+        graphics_queue.device().clone(),
+        attachments: {
+            color: {
+                load: Clear,
+                store: Store,
+                format: Format::B8G8R8A8_SRGB,
+                samples: 1,
+            },
+            element_id: {
+                load: Clear,
+                store: Store,
+                format: Format::R32_UINT,
+                samples: 1,
+            }
+        },
+        pass: {
+            color: [color, element_id],
+            depth_stencil: {}
+        }
+    )
+    .unwrap();
+    let subpass = Subpass::from(Arc::new(render_pass), 0).unwrap();
+    UiDrawSystem::with_color_attachments(
+        graphics_queue,
+        subpass,
+        1,
+        UiColorAttachments::ColorAndElementId,
+    )
+    .unwrap();
+}
+
+#[test]
+#[ignore = "requires Vulkan device"]
+fn test_parallel_record() {
+    let graphics_queue = graphics_queue();
+    let render_pass = ui_render_pass(&graphics_queue);
+    let subpass = Subpass::from(render_pass, 0).unwrap();
+    let mut system = UiDrawSystem::new(graphics_queue, subpass, 2).unwrap();
+    let texture = Arc::new(Texture {
+        version: 0,
+        width: 1,
+        height: 1,
+        pixels: vec![u8::MAX],
+    });
+    system.prepare(texture).unwrap();
+    system.set_budget(UiDrawBudget {
+        max_meshes: Some(2),
+        ..UiDrawBudget::default()
+    });
+
+    // Two threads record UI of their viewports with shared system at the same time,
+    // and each recorder keeps statistics of its own thread.
+    let system = Arc::new(system);
+    let threads: Vec<_> = [1, 3]
+        .into_iter()
+        .map(|mesh_count| {
+            let system = system.clone();
+            std::thread::spawn(move || {
+                let mut recorder = system.create_recorder();
+                let clip_rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(100.0, 100.0));
+                let meshes = (0..mesh_count)
+                    .map(|_| {
+                        let white = mesh(&[Color32::WHITE; 3]);
+                        (ClippedMesh(clip_rect, white), UiBlendMode::Alpha)
+                    })
+                    .collect();
+                let viewport_size = PhysicalSize::new(800, 600);
+                system
+                    .record(&mut recorder, viewport_size, 1.0, meshes, 0, None)
+                    .unwrap();
+                recorder.stats()
+            })
+        })
+        .collect();
+    let dropped_meshes: Vec<_> = threads
+        .into_iter()
+        .map(|thread| thread.join().unwrap().dropped_meshes)
+        .collect();
+    assert_eq!(dropped_meshes, [0, 1]);
+}
+
+#[test]
+fn test_tag_meshes() {
+    let meshes = vec![
+        (
+            ClippedMesh(Rect::EVERYTHING, mesh(&[Color32::WHITE])),
+            UiBlendMode::Alpha,
+        ),
+        (
+            ClippedMesh(Rect::EVERYTHING, mesh(&[Color32::WHITE])),
+            UiBlendMode::Additive,
+        ),
+    ];
+    let ((element_ids, blend_modes), meshes) = tag_meshes(meshes, None);
+    assert_eq!(element_ids, [1, 2]);
+    assert_eq!(blend_modes, [UiBlendMode::Alpha, UiBlendMode::Additive]);
+    assert_eq!(meshes.len(), 2);
+}
+
+#[test]
+fn test_is_drawable() {
+    let visible = mesh(&[Color32::WHITE; 3]);
+    let transparent = mesh(&[Color32::TRANSPARENT; 3]);
+    assert!(is_drawable(&visible, true));
+    assert!(is_drawable(&transparent, false));
+    assert!(!is_drawable(&transparent, true));
+    assert!(!is_drawable(&Mesh::default(), false));
+}
+
+#[test]
+fn test_texture_samplers() {
+    let created = samplers(|filter, mipmap_mode, address_mode| {
+        Ok::<_, ()>((filter, mipmap_mode, address_mode))
+    })
+    .unwrap();
+    assert_eq!(created.len(), TextureOptions::ALL.len());
+
+    // Pixel art and photo in the same frame are sampled by distinct samplers.
+    let nearest = TextureOptions::new(TextureFilter::Nearest, TextureWrapMode::ClampToEdge);
+    let linear = TextureOptions::new(TextureFilter::Linear, TextureWrapMode::ClampToEdge);
+    assert_eq!(
+        created[&nearest],
+        (
+            Filter::Nearest,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge
+        ),
+    );
+    assert_eq!(
+        created[&linear],
+        (
+            Filter::Linear,
+            MipmapMode::Linear,
+            SamplerAddressMode::ClampToEdge
+        ),
+    );
+    let repeat = TextureOptions::new(TextureFilter::Linear, TextureWrapMode::Repeat);
+    assert_eq!(created[&repeat].2, SamplerAddressMode::Repeat);
+    assert_eq!(created[&TextureOptions::default()], created[&linear]);
+
+    assert_eq!(
+        samplers(|_, _, _| Err::<(), _>("out of memory")),
+        Err("out of memory")
+    );
+}
+
+#[test]
+fn test_bindless_texture_index() {
+    // Bindless textures are registered into the only large texture array.
+    let reserved = EGUI_INDEX as usize + 1;
+    let mut texture_array = TextureArray::new(0_u32, TEXTURE_ARRAY_LEN, reserved);
+    let index = (1..=5)
+        .map(|image| texture_array.insert(image).unwrap())
+        .last()
+        .unwrap();
+    assert_eq!(index, 5);
+
+    let mut user_textures = SlotMap::new();
+    let key = user_textures.insert(UserTexture {
+        binding: TextureBinding::ArrayElement { array: 0, index },
+        description: ImageDescription {
+            dimensions: ImageDimensions::Dim2d {
+                width: 1,
+                height: 1,
+                array_layers: 1,
+            },
+            format: Format::R8G8B8A8_SRGB,
+        },
+        last_drawn: AtomicU64::new(0),
+        msdf: None,
+        uv_transform: UvTransform::IDENTITY,
+    });
+    let texture_id = TextureId::User(key.data().as_ffi());
+
+    // Index of the array element comes back out of the texture identifier
+    // and is pushed with the mesh, so that shader selects the texture.
+    let id = match texture_id {
+        TextureId::User(id) => id,
+        TextureId::Egui => unreachable!(),
+    };
+    let texture = UiDrawSystem::user_texture(&user_textures, 1, id).unwrap();
+    assert_eq!(texture.last_drawn.load(Ordering::Relaxed), 1);
+    let texture_index = match texture.binding {
+        TextureBinding::ArrayElement { array, index } => {
+            assert_eq!(array, 0);
+            index
+        }
+        TextureBinding::DescriptorSet(_) => panic!("bindless texture has its own descriptor set"),
+    };
+    let constants = push_constants(
+        [800.0, 600.0],
+        YAxis::Down,
+        None,
+        Default::default(),
+        Default::default(),
+    );
+    let constants = mesh_push_constants(constants, texture_index, None, 2);
+    assert_eq!(constants.texture_index, index);
+    assert_eq!(constants.element_id, 2);
+}
+
+#[test]
+fn test_unregistered_user_texture() {
+    let mut user_textures = SlotMap::<DefaultKey, UserTexture>::new();
+    let key = user_textures.insert(UserTexture {
+        binding: TextureBinding::ArrayElement { array: 0, index: 1 },
+        description: ImageDescription {
+            dimensions: ImageDimensions::Dim2d {
+                width: 1,
+                height: 1,
+                array_layers: 1,
+            },
+            format: Format::R8G8B8A8_SRGB,
+        },
+        last_drawn: AtomicU64::new(0),
+        msdf: None,
+        uv_transform: UvTransform::IDENTITY,
+    });
+    let id = key.data().as_ffi();
+    user_textures.remove(key);
+
+    // Evicted texture still used by `egui` is skipped instead of panicking.
+    assert!(UiDrawSystem::user_texture(&user_textures, 1, id).is_none());
+    let binding = UiDrawSystem::texture_binding(&user_textures, 1, TextureId::User(id), &[]);
+    assert!(binding.is_none());
+}
+
+#[test]
+#[ignore = "requires Vulkan device"]
+fn test_record_levels() {
+    let graphics_queue = graphics_queue();
+    let device = graphics_queue.device().clone();
+    let render_pass = ui_render_pass(&graphics_queue);
+    let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+    let mut system = UiDrawSystem::new(graphics_queue.clone(), subpass, 1).unwrap();
+    system.set_skip_transparent(true);
+    let texture = Arc::new(Texture {
+        version: 0,
+        width: 1,
+        height: 1,
+        pixels: vec![u8::MAX],
+    });
+    let viewport_size = PhysicalSize::new(800, 600);
+    let clip_rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(100.0, 100.0));
+    // Transparent mesh is skipped by both paths.
+    let meshes = || {
+        [Color32::WHITE, Color32::TRANSPARENT, Color32::RED]
+            .iter()
+            .map(|&color| ClippedMesh(clip_rect, mesh(&[color; 3])))
+            .collect::<Vec<_>>()
+    };
+
+    // Secondary draw and primary record share one mesh loop.
+    system
+        .draw(viewport_size, 1.0, meshes(), texture.clone(), 0, None)
+        .unwrap();
+    let secondary = system.stats();
+
+    let image = AttachmentImage::new(device.clone(), [800, 600], Format::B8G8R8A8_SRGB).unwrap();
+    let framebuffer = Framebuffer::start(render_pass)
+        .add(ImageView::new(image).unwrap())
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut builder = AutoCommandBufferBuilder::primary(
+        device,
+        graphics_queue.family(),
+        CommandBufferUsage::OneTimeSubmit,
+    )
+    .unwrap();
+    builder
+        .begin_render_pass(
+            Arc::new(framebuffer),
+            SubpassContents::Inline,
+            [ClearValue::Float([0.0, 0.0, 0.0, 0.0])],
+        )
+        .unwrap();
+    system
+        .record_into(&mut builder, viewport_size, 1.0, meshes(), texture, 0, None)
+        .unwrap();
+    let primary = system.stats();
+
+    assert_eq!(secondary.drawn_meshes, 2);
+    assert_eq!(primary, secondary);
+}
+
+/// Command buffer builder which counts commands of dynamic state.
+struct CountingBuilder<L> {
+    builder: AutoCommandBufferBuilder<L>,
+    viewports: usize,
+    scissors: usize,
+}
+
+impl<L> UiCommandBuilder for CountingBuilder<L> {
+    type Level = L;
+
+    fn builder(&mut self) -> &mut AutoCommandBufferBuilder<L> {
+        &mut self.builder
+    }
+
+    fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewports += 1;
+        self.builder.set_viewport(0, iter::once(viewport));
+    }
+
+    fn set_scissor(&mut self, scissor: Scissor) {
+        self.scissors += 1;
+        self.builder.set_scissor(0, iter::once(scissor));
+    }
+}
+
+#[test]
+#[ignore = "requires Vulkan device"]
+fn test_viewport_per_draw() {
+    let graphics_queue = graphics_queue();
+    let render_pass = ui_render_pass(&graphics_queue);
+    let subpass = Subpass::from(render_pass, 0).unwrap();
+    let mut system = UiDrawSystem::new(graphics_queue.clone(), subpass.clone(), 1).unwrap();
+    let texture = Arc::new(Texture {
+        version: 0,
+        width: 1,
+        height: 1,
+        pixels: vec![u8::MAX],
+    });
+    system.prepare(texture).unwrap();
+
+    let builder = AutoCommandBufferBuilder::secondary_graphics(
+        graphics_queue.device().clone(),
+        graphics_queue.family(),
+        CommandBufferUsage::OneTimeSubmit,
+        subpass,
+    )
+    .unwrap();
+    let mut builder = CountingBuilder {
+        builder,
+        viewports: 0,
+        scissors: 0,
+    };
+    let viewport_size = PhysicalSize::new(800, 600);
+    let clip_rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(100.0, 100.0));
+    let meshes = [Color32::WHITE, Color32::RED, Color32::BLUE]
+        .iter()
+        .map(|&color| {
+            (
+                ClippedMesh(clip_rect, mesh(&[color; 3])),
+                UiBlendMode::default(),
+            )
+        })
+        .collect();
+    let stats = system
+        .record_ui(
+            &mut builder,
+            &system.frame_buffers[0],
+            viewport_size,
+            1.0,
+            meshes,
+            None,
+        )
+        .unwrap();
+
+    // Viewport is the same for all meshes, so only scissor is set per mesh.
+    assert_eq!(stats.drawn_meshes, 3);
+    assert_eq!(builder.viewports, 1);
+    assert_eq!(builder.scissors, 3);
+}
+
+#[test]
+fn test_invalidate_textures() {
+    let reserved = EGUI_INDEX as usize + 1;
+    let mut user_textures = SlotMap::new();
+    let mut texture_arrays = vec![
+        TextureArray::new(0_u32, TEXTURE_GROUP_LEN, reserved),
+        TextureArray::new(0_u32, TEXTURE_GROUP_LEN, 0),
+    ];
+    texture_arrays[0].set(EGUI_INDEX, 1);
+    for image in 2..=4 {
+        let index = texture_arrays[0].insert(image).unwrap();
+        user_textures.insert(index);
+    }
+    let index = texture_arrays[1].insert(5).unwrap();
+    user_textures.insert(index);
+
+    let texture = Arc::new(Texture {
+        version: 0,
+        width: 1,
+        height: 1,
+        pixels: vec![u8::MAX],
+    });
+    let mut uploaded_texture = Some(texture.clone());
+    assert!(!is_texture_changed(uploaded_texture.as_ref(), &texture));
+
+    reset_textures(
+        &mut user_textures,
+        &mut texture_arrays,
+        &mut uploaded_texture,
+    );
+    assert!(user_textures.is_empty());
+    assert_eq!(texture_arrays.len(), 1);
+    assert!(texture_arrays[0].is_empty());
+    assert_eq!(texture_arrays[0].insert(6), Some(1));
+
+    // The same base texture is uploaded again by the next draw.
+    assert!(is_texture_changed(uploaded_texture.as_ref(), &texture));
+}
+
+#[test]
+fn test_group_by_descriptor_set() {
+    let textured = |id, texture_id| {
+        let mut mesh = mesh(&[Color32::WHITE; 3]);
+        mesh.texture_id = texture_id;
+        (id, ClippedMesh(Rect::EVERYTHING, mesh))
+    };
+    // Base texture and two user images share the first texture array,
+    // and the third user image (e.g. MSDF atlas) has its own descriptor set.
+    let descriptor_set = |texture_id| match texture_id {
+        TextureId::Egui | TextureId::User(1) | TextureId::User(2) => 0,
+        TextureId::User(_) => 1,
+    };
+    let binds = |tagged_meshes: &[(u32, ClippedMesh)]| {
+        let descriptor_sets: Vec<_> = tagged_meshes
+            .iter()
+            .map(|(_, ClippedMesh(_, mesh))| descriptor_set(mesh.texture_id))
+            .collect();
+        1 + descriptor_sets
+            .windows(2)
+            .filter(|pair| pair[0] != pair[1])
+            .count()
+    };
+
+    // Text alternates with user images.
+    let mut tagged_meshes = vec![
+        textured(1, TextureId::Egui),
+        textured(2, TextureId::User(3)),
+        textured(3, TextureId::User(1)),
+        textured(4, TextureId::User(3)),
+        textured(5, TextureId::Egui),
+        textured(6, TextureId::User(2)),
+    ];
+    assert_eq!(binds(&tagged_meshes), 5);
+
+    group_by_descriptor_set(&mut tagged_meshes, descriptor_set);
+    assert_eq!(binds(&tagged_meshes), 2);
+    let ids: Vec<_> = tagged_meshes.iter().map(|&(id, _)| id).collect();
+    assert_eq!(ids, [1, 3, 5, 6, 2, 4]);
+}
+
+#[test]
+fn test_outline_text() {
+    let textured = |id, texture_id| {
+        let mut mesh = mesh(&[Color32::WHITE; 3]);
+        mesh.texture_id = texture_id;
+        (id, ClippedMesh(Rect::EVERYTHING, mesh))
+    };
+    let text_outline = TextOutline::outline(Color32::BLACK, 1.0);
+    let samples = text_outline.samples as usize;
+
+    let tagged_meshes = vec![
+        textured(1, TextureId::Egui),
+        textured(2, TextureId::User(1)),
+    ];
+    let outlined = outline_text(tagged_meshes, &text_outline);
+    assert_eq!(outlined.len(), samples + 1 + 1);
+
+    // Shadows of the text mesh precede it and keep its tag.
+    let ids: Vec<_> = outlined.iter().map(|&(id, _)| id).collect();
+    assert!(ids[..samples + 1].iter().all(|&id| id == 1));
+    assert_eq!(ids[samples + 1], 2);
+    let ClippedMesh(_, shadow) = &outlined[0].1;
+    assert_eq!(shadow.vertices[0].color, Color32::BLACK);
+    assert_eq!(shadow.vertices[0].pos, Pos2::new(1.0, 0.0));
+    let ClippedMesh(_, text) = &outlined[samples].1;
+    assert_eq!(text.vertices[0].color, Color32::WHITE);
+
+    let shadow = TextOutline::shadow(Color32::BLACK, Vec2::new(1.0, 1.0));
+    assert_eq!(
+        shadow.sample_offsets().collect::<Vec<_>>(),
+        [Vec2::new(1.0, 1.0)]
+    );
+}
+
+#[test]
+fn test_persistent_capacity() {
+    // Buffer is allocated for the first draw and reused while vertices fit.
+    assert_eq!(persistent_capacity(0, 100), 128);
+    assert_eq!(persistent_capacity(128, 100), 128);
+    assert_eq!(persistent_capacity(128, 128), 128);
+    assert_eq!(persistent_capacity(128, 10), 128);
+
+    // Buffer grows only when vertices do not fit.
+    assert_eq!(persistent_capacity(128, 129), 256);
+}
+
+#[test]
+fn test_persistent_reuse_in_frame() {
+    // Buffer of the frame, which is filled by the first draw of the frame.
+    let buffer = Arc::new(vec![UiVertex::default(); 128]);
+    assert!(is_reusable(&buffer, buffer.len(), 128));
+    assert!(!is_reusable(&buffer, buffer.len(), 256));
+
+    // Command buffer of the first draw is recorded, but not submitted yet,
+    // so the second draw of the same frame must not overwrite its vertices.
+    let first_draw = buffer.clone();
+    assert!(!is_reusable(&buffer, buffer.len(), 128));
+
+    // Buffer is reused when the command buffer is dropped after GPU finished the frame.
+    drop(first_draw);
+    assert!(is_reusable(&buffer, buffer.len(), 128));
+}
+
+#[test]
+fn test_eviction_candidate() {
+    let capacity = Some(3);
+    let mut draw_counter = 0;
+    let mut textures = SlotMap::<DefaultKey, u64>::new();
+    let mut register = |textures: &mut SlotMap<DefaultKey, u64>, protected_since| {
+        let count = textures.len();
+        let candidate = eviction_candidate(
+            textures.iter().map(|(key, &last_drawn)| (key, last_drawn)),
+            count,
+            capacity,
+            protected_since,
+        );
+        if let Some(key) = candidate {
+            textures.remove(key);
+        }
+        draw_counter += 1;
+        (textures.insert(draw_counter), candidate)
+    };
+
+    let (oldest, _) = register(&mut textures, u64::MAX);
+    let (second, _) = register(&mut textures, u64::MAX);
+    let (third, evicted) = register(&mut textures, u64::MAX);
+    assert_eq!(evicted, None);
+
+    // Registering past the capacity evicts the oldest texture.
+    let (_, evicted) = register(&mut textures, u64::MAX);
+    assert_eq!(evicted, Some(oldest));
+    assert_eq!(textures.len(), 3);
+
+    // Drawn texture becomes the most recent one, so the next oldest is evicted.
+    textures[second] = 10;
+    let (_, evicted) = register(&mut textures, u64::MAX);
+    assert_eq!(evicted, Some(third));
+
+    // Recently drawn textures are never evicted, even if capacity is exceeded.
+    let (_, evicted) = register(&mut textures, 4);
+    assert_eq!(evicted, None);
+    assert_eq!(textures.len(), 4);
+
+    let textures = textures.iter().map(|(key, &last_drawn)| (key, last_drawn));
+    assert_eq!(eviction_candidate(textures, 3, None, u64::MAX), None);
+}
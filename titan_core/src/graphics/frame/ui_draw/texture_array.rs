@@ -0,0 +1,131 @@
+//! Descriptor arrays of textures for UI rendering.
+
+use std::sync::Arc;
+
+use vulkano::descriptor_set::layout::DescriptorSetLayout;
+use vulkano::descriptor_set::{DescriptorSet, PersistentDescriptorSet};
+use vulkano::image::ImageViewAbstract;
+use vulkano::sampler::Sampler;
+
+use crate::graphics::renderer::error::DescriptorSetCreationError;
+
+/// Length of the texture array if bindless textures are used.
+///
+/// Must be equal to the length of `textures` array in `ui_bindless.frag` shader.
+///
+pub const TEXTURE_ARRAY_LEN: usize = 128;
+
+/// Length of each texture array if bindless textures are not used.
+///
+/// Must be equal to the length of `textures` array in `ui.frag` shader.
+///
+pub const TEXTURE_GROUP_LEN: usize = 4;
+
+/// Index of `egui` base texture in the first texture array.
+pub const EGUI_INDEX: u32 = 0;
+
+/// Image view with the sampler which is used to sample it.
+pub type SampledImage = (Arc<dyn ImageViewAbstract + Send + Sync>, Arc<Sampler>);
+
+/// Descriptor array of textures drawn in UI.
+///
+/// Several textures are bound at once with one descriptor set,
+/// and texture of each mesh is selected by its index in the array.
+/// Leading elements of the array could be reserved (e.g. for `egui` base texture),
+/// so they are never used by [`insert`](TextureArray::insert).
+///
+pub struct TextureArray<T = SampledImage> {
+    /// Elements of the array.
+    elements: Vec<Option<T>>,
+
+    /// Count of leading elements of the array which are reserved.
+    reserved: usize,
+
+    /// Image which is bound to empty elements of the array.
+    placeholder: T,
+
+    /// Descriptor set of the array; `None` if the array was changed since the last build.
+    descriptor_set: Option<Arc<dyn DescriptorSet + Send + Sync>>,
+}
+
+impl<T: Clone> TextureArray<T> {
+    /// Creates new texture array of given length with all elements bound to the placeholder.
+    pub fn new(placeholder: T, len: usize, reserved: usize) -> Self {
+        Self {
+            elements: vec![None; len],
+            reserved,
+            placeholder,
+            descriptor_set: None,
+        }
+    }
+
+    /// Inserts image into the first free element of the array which is not reserved.
+    ///
+    /// Returns `None` if there are no free elements.
+    ///
+    pub fn insert(&mut self, image: T) -> Option<u32> {
+        let index = self
+            .elements
+            .iter()
+            .skip(self.reserved)
+            .position(Option::is_none)?
+            + self.reserved;
+        self.set(index as u32, image);
+        Some(index as u32)
+    }
+
+    /// Replaces image in the element of the array with given index.
+    pub fn set(&mut self, index: u32, image: T) {
+        self.elements[index as usize] = Some(image);
+        self.descriptor_set = None;
+    }
+
+    /// Frees the element of the array with given index.
+    pub fn remove(&mut self, index: u32) {
+        self.elements[index as usize] = None;
+        self.descriptor_set = None;
+    }
+
+    /// Frees all elements of the array, including reserved ones.
+    pub fn clear(&mut self) {
+        self.elements.iter_mut().for_each(|element| *element = None);
+        self.descriptor_set = None;
+    }
+
+    /// Checks if there are no images in the array except reserved ones.
+    pub fn is_empty(&self) -> bool {
+        self.elements
+            .iter()
+            .skip(self.reserved)
+            .all(Option::is_none)
+    }
+}
+
+impl<T> TextureArray<T> {
+    /// Retrieves descriptor set of the array if it was built after the last change of the array.
+    pub fn built_descriptor_set(&self) -> Option<Arc<dyn DescriptorSet + Send + Sync>> {
+        self.descriptor_set.clone()
+    }
+}
+
+impl TextureArray {
+    /// Retrieves descriptor set of the array, rebuilding it if the array was changed.
+    pub fn descriptor_set(
+        &mut self,
+        layout: Arc<DescriptorSetLayout>,
+    ) -> Result<Arc<dyn DescriptorSet + Send + Sync>, DescriptorSetCreationError> {
+        if let Some(descriptor_set) = &self.descriptor_set {
+            return Ok(descriptor_set.clone());
+        }
+        let mut builder = PersistentDescriptorSet::start(layout);
+        builder.enter_array()?;
+        for element in &self.elements {
+            let (image_view, sampler) = element.as_ref().unwrap_or(&self.placeholder).clone();
+            builder.add_sampled_image(image_view, sampler)?;
+        }
+        builder.leave_array()?;
+        let descriptor_set: Arc<dyn DescriptorSet + Send + Sync> = Arc::new(builder.build()?);
+        self.descriptor_set = Some(descriptor_set.clone());
+        Ok(descriptor_set)
+    }
+}
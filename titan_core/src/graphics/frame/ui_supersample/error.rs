@@ -0,0 +1,63 @@
+use thiserror::Error;
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilderContextError, BeginRenderPassError, BlitImageError, BuildError,
+    CommandBufferExecError, ExecuteCommandsError,
+};
+use vulkano::image::view::ImageViewCreationError;
+use vulkano::image::ImageCreationError;
+use vulkano::render_pass::{FramebufferCreationError, RenderPassCreationError};
+use vulkano::sync::FlushError;
+use vulkano::OomError;
+
+use crate::graphics::frame::ui_draw::error::{UiDrawError, UiDrawSystemCreationError};
+
+#[derive(Debug, Error)]
+pub enum UiSupersamplerCreationError {
+    #[error("supersample factor {0} must be finite and not less than 1.0")]
+    InvalidFactor(f32),
+
+    #[error("render pass creation failure: {0}")]
+    RenderPassCreation(#[from] RenderPassCreationError),
+
+    #[error("UI draw system creation failure: {0}")]
+    UiDrawSystemCreation(#[from] UiDrawSystemCreationError),
+}
+
+#[derive(Debug, Error)]
+pub enum UiSupersampleError {
+    #[error("command buffer allocation failure: {0}")]
+    OutOfMemory(#[from] OomError),
+
+    #[error("offscreen image creation failure: {0}")]
+    ImageCreation(#[from] ImageCreationError),
+
+    #[error("offscreen image view creation failure: {0}")]
+    ImageViewCreation(#[from] ImageViewCreationError),
+
+    #[error("offscreen framebuffer creation failure: {0}")]
+    FramebufferCreation(#[from] FramebufferCreationError),
+
+    #[error("UI draw failure: {0}")]
+    UiDraw(#[from] UiDrawError),
+
+    #[error("begin render pass command failure: {0}")]
+    BeginRenderPass(#[from] BeginRenderPassError),
+
+    #[error("UI command buffer execution failure: {0}")]
+    ExecuteCommands(#[from] ExecuteCommandsError),
+
+    #[error("end render pass command failure: {0}")]
+    WrongUsage(#[from] AutoCommandBufferBuilderContextError),
+
+    #[error("downsampling blit command failure: {0}")]
+    BlitImage(#[from] BlitImageError),
+
+    #[error("supersample command buffer build failure: {0}")]
+    CommandBufferBuild(#[from] BuildError),
+
+    #[error("supersample command buffer execution failure: {0}")]
+    CommandBufferExec(#[from] CommandBufferExecError),
+
+    #[error("supersample submission failure: {0}")]
+    Flush(#[from] FlushError),
+}
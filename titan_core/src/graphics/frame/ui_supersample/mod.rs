@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use egui::{ClippedMesh, Texture};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage, SubpassContents};
+use vulkano::device::Queue;
+use vulkano::format::{ClearValue, Format};
+use vulkano::image::view::ImageView;
+use vulkano::image::{AttachmentImage, ImageAccess, ImageUsage};
+use vulkano::render_pass::{Framebuffer, FramebufferAbstract, RenderPass, Subpass};
+use vulkano::sampler::Filter;
+use vulkano::sync::GpuFuture;
+
+use crate::{
+    graphics::frame::{
+        ui_draw::UiDrawSystem,
+        ui_supersample::error::{UiSupersampleError, UiSupersamplerCreationError},
+    },
+    window::PhysicalSize,
+};
+
+pub mod error;
+
+mod tests;
+
+/// Format of the offscreen image which UI is rendered into.
+const SUPERSAMPLE_FORMAT: Format = Format::R8G8B8A8_SRGB;
+
+/// Size of one texel of the offscreen image in bytes.
+const TEXEL_SIZE: u64 = 4;
+
+/// Offscreen image of one frame in flight with its framebuffer.
+struct Offscreen {
+    /// Size of the image.
+    size: PhysicalSize,
+
+    /// Image which UI is rendered into.
+    image: Arc<AttachmentImage>,
+
+    /// Framebuffer with the image as single color attachment.
+    framebuffer: Arc<dyn FramebufferAbstract + Send + Sync>,
+}
+
+/// System that renders UI at higher internal resolution and downsamples it into the target,
+/// which makes small fonts sharper on low-DPI screens at the cost of fill rate.
+///
+/// Offscreen image of each frame in flight is `supersample_factor` times larger
+/// than the target in each dimension, so VRAM cost grows with the square of the factor:
+/// at factor 2.0, UI of 1920x1080 target needs about 33 MB per frame in flight
+/// (see [`vram_cost`](UiSupersampler::vram_cost)).
+///
+/// Render pass of this system is not compatible with the render pass of the frame,
+/// so it has its own [`UiDrawSystem`]: user textures must be registered in it separately.
+///
+pub struct UiSupersampler {
+    /// Queue to render on.
+    graphics_queue: Arc<Queue>,
+
+    /// Render pass with single color attachment used for the offscreen image.
+    render_pass: Arc<RenderPass>,
+
+    /// System that draws UI in the render pass of the offscreen image.
+    ui_draw_system: UiDrawSystem,
+
+    /// Scale of the offscreen image relative to the target.
+    factor: f32,
+
+    /// Offscreen image of each frame in flight, created on first use.
+    offscreens: Vec<Option<Offscreen>>,
+}
+
+impl UiSupersampler {
+    /// Creates new UI supersampling system with given supersample factor.
+    ///
+    /// Factor must not be less than 1.0, which turns supersampling off
+    /// (UI is still rendered offscreen and copied into the target).
+    ///
+    pub fn new(
+        graphics_queue: Arc<Queue>,
+        factor: f32,
+        frames_in_flight: usize,
+    ) -> Result<Self, UiSupersamplerCreationError> {
+        if !factor.is_finite() || factor < 1.0 {
+            return Err(UiSupersamplerCreationError::InvalidFactor(factor));
+        }
+        let render_pass = Arc::new(vulkano::single_pass_renderpass!(
+            graphics_queue.device().clone(),
+            attachments: {
+                color: {
+                    load: Clear,
+                    store: Store,
+                    format: SUPERSAMPLE_FORMAT,
+                    samples: 1,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        )?);
+        let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+        let frames_in_flight = frames_in_flight.max(1);
+        let ui_draw_system = UiDrawSystem::new(graphics_queue.clone(), subpass, frames_in_flight)?;
+
+        Ok(Self {
+            graphics_queue,
+            render_pass,
+            ui_draw_system,
+            factor,
+            offscreens: (0..frames_in_flight).map(|_| None).collect(),
+        })
+    }
+
+    /// System that draws UI into the offscreen image.
+    ///
+    /// Meshes must be in logical points: drawing of meshes in physical pixels
+    /// is not scaled by the supersample factor.
+    ///
+    pub fn ui_draw_system(&mut self) -> &mut UiDrawSystem {
+        &mut self.ui_draw_system
+    }
+
+    /// Scale of the offscreen image relative to the target.
+    pub fn factor(&self) -> f32 {
+        self.factor
+    }
+
+    /// If UI is rendered at higher resolution than the target.
+    ///
+    /// If not, it is cheaper to draw UI into the target directly.
+    ///
+    pub fn is_enabled(&self) -> bool {
+        self.factor > 1.0
+    }
+
+    /// Size of the offscreen image for the target of given size.
+    pub fn supersampled_size(&self, target_size: PhysicalSize) -> PhysicalSize {
+        self::supersampled_size(target_size, self.factor)
+    }
+
+    /// Memory in bytes occupied by offscreen images of all frames in flight
+    /// for the target of given size.
+    pub fn vram_cost(&self, target_size: PhysicalSize) -> u64 {
+        let size = self.supersampled_size(target_size);
+        let frame_cost = size.width as u64 * size.height as u64 * TEXEL_SIZE;
+        frame_cost * self.offscreens.len() as u64
+    }
+
+    /// Renders UI into the offscreen image and downsamples it into the target image.
+    ///
+    /// Commands are executed after `wait` future, then semaphore is signaled
+    /// and all the commands are flushed.
+    ///
+    /// Downsampling is done by blit with linear filter, which is a box filter for factor 2.0;
+    /// higher factors skip some texels of the offscreen image.
+    /// Target is overwritten (UI background is fully transparent), so it should be
+    /// a separate UI layer which is composed later. It must be created
+    /// with `transfer_destination` usage and a format which supports blitting.
+    ///
+    pub fn render<F, I>(
+        &mut self,
+        wait: F,
+        meshes: Vec<ClippedMesh>,
+        texture: Arc<Texture>,
+        target: I,
+        scale_factor: f32,
+        frame_index: usize,
+    ) -> Result<Box<dyn GpuFuture + Send + Sync>, UiSupersampleError>
+    where
+        F: GpuFuture + Send + Sync + 'static,
+        I: ImageAccess + Send + Sync + 'static,
+    {
+        let [width, height] = target.dimensions().width_height();
+        let size = self.supersampled_size(PhysicalSize::new(width, height));
+        let index = frame_index % self.offscreens.len();
+        let offscreen = match self.offscreens[index].take() {
+            Some(offscreen) if offscreen.size == size => offscreen,
+            _ => self.create_offscreen(size)?,
+        };
+        let image = offscreen.image.clone();
+        let framebuffer = offscreen.framebuffer.clone();
+        self.offscreens[index] = Some(offscreen);
+
+        let ui_command_buffer = self.ui_draw_system.draw(
+            size,
+            scale_factor * self.factor,
+            meshes,
+            texture,
+            frame_index,
+            None,
+        )?;
+        let mut builder = AutoCommandBufferBuilder::primary(
+            self.graphics_queue.device().clone(),
+            self.graphics_queue.family(),
+            CommandBufferUsage::OneTimeSubmit,
+        )?;
+        builder
+            .begin_render_pass(
+                framebuffer,
+                SubpassContents::SecondaryCommandBuffers,
+                [ClearValue::Float([0.0, 0.0, 0.0, 0.0])],
+            )?
+            .execute_commands(ui_command_buffer)?
+            .end_render_pass()?
+            .blit_image(
+                image,
+                [0, 0, 0],
+                [size.width as i32, size.height as i32, 1],
+                0,
+                0,
+                target,
+                [0, 0, 0],
+                [width as i32, height as i32, 1],
+                0,
+                0,
+                1,
+                Filter::Linear,
+            )?;
+        let command_buffer = builder.build()?;
+
+        let future = wait
+            .then_execute(self.graphics_queue.clone(), command_buffer)?
+            .then_signal_semaphore_and_flush()?;
+        Ok(Box::new(future))
+    }
+
+    /// Creates offscreen image of given size with its framebuffer.
+    fn create_offscreen(&self, size: PhysicalSize) -> Result<Offscreen, UiSupersampleError> {
+        let image = AttachmentImage::with_usage(
+            self.graphics_queue.device().clone(),
+            size.into(),
+            SUPERSAMPLE_FORMAT,
+            ImageUsage {
+                color_attachment: true,
+                transfer_source: true,
+                ..ImageUsage::none()
+            },
+        )?;
+        let framebuffer = Arc::new(
+            Framebuffer::start(self.render_pass.clone())
+                .add(ImageView::new(image.clone())?)?
+                .build()?,
+        );
+        Ok(Offscreen {
+            size,
+            image,
+            framebuffer,
+        })
+    }
+}
+
+/// Size of the offscreen image for the target of given size and supersample factor.
+fn supersampled_size(target_size: PhysicalSize, factor: f32) -> PhysicalSize {
+    let scale = |extent: u32| ((extent as f32 * factor).round() as u32).max(1);
+    PhysicalSize::new(scale(target_size.width), scale(target_size.height))
+}
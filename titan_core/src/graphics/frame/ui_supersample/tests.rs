@@ -0,0 +1,23 @@
+#![cfg(test)]
+
+use super::*;
+
+#[test]
+fn test_supersampled_size() {
+    let target_size = PhysicalSize::new(1920, 1080);
+    assert_eq!(supersampled_size(target_size, 1.0), target_size);
+    assert_eq!(
+        supersampled_size(target_size, 2.0),
+        PhysicalSize::new(3840, 2160)
+    );
+    assert_eq!(
+        supersampled_size(target_size, 1.5),
+        PhysicalSize::new(2880, 1620)
+    );
+
+    // Offscreen image is never empty.
+    assert_eq!(
+        supersampled_size(PhysicalSize::new(0, 1), 2.0),
+        PhysicalSize::new(1, 2)
+    );
+}
@@ -0,0 +1,17 @@
+//! Instance utilities which are available before instance creation.
+//!
+//! Vulkan library is loaded on first call of any of these functions
+//! and then cached for the whole lifetime of the process.
+
+use vulkano::extensions::SupportedExtensionsError;
+use vulkano::instance::{layers_list, InstanceExtensions, LayerProperties, LayersListError};
+
+/// Enumerates all instance layers available on the system.
+pub fn available_layers() -> Result<Vec<LayerProperties>, LayersListError> {
+    Ok(layers_list()?.collect())
+}
+
+/// Retrieves all instance extensions supported by the core Vulkan library.
+pub fn available_extensions() -> Result<InstanceExtensions, SupportedExtensionsError> {
+    InstanceExtensions::supported_by_core()
+}
@@ -1,12 +1,35 @@
 //! Graphics utilities and backend based on Vulkan API for game engine.
 
+pub use self::debug_callback::set_object_name;
+pub use self::frame::blur::{
+    error::{BlurBehindCreationError, BlurError},
+    BlurBehind,
+};
+pub use self::frame::ui_capture::{
+    error::{UiCaptureCreationError, UiCaptureError},
+    UiCapture,
+};
+pub use self::frame::ui_draw::{
+    error::{UiSrgbViewError, UiSubmitError},
+    submit_ui, ui_srgb_view, ColorFilter, ImageDescription, MsdfOptions, TextOutline,
+    TextureFilter, TextureOptions, TextureWrapMode, UiBlendMode, UiColorAttachments, UiDrawBudget,
+    UiDrawStats, UiDrawSystem, UiRecorder, UiRotation, UvTransform, YAxis,
+};
+pub use self::frame::ui_supersample::{
+    error::{UiSupersampleError, UiSupersamplerCreationError},
+    UiSupersampler,
+};
 pub use self::renderer::*;
+pub use self::vertex::UiInstance;
 
 pub(crate) mod camera;
+pub mod instance;
+pub mod surface;
 
 mod debug_callback;
 mod frame;
 mod renderer;
 mod shader;
+mod tests;
 mod utils;
 mod vertex;
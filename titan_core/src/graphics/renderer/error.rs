@@ -9,21 +9,24 @@
 use vulkano::instance::debug::DebugCallbackCreationError;
 use vulkano::instance::InstanceCreationError;
 use vulkano::memory::DeviceMemoryAllocError;
-use vulkano::swapchain::{AcquireError, CapabilitiesError, SwapchainCreationError};
+use vulkano::swapchain::{AcquireError, CapabilitiesError, PresentMode, SwapchainCreationError};
 use vulkano::sync::FlushError;
-use vulkano::OomError;
+use vulkano::{OomError, Version};
 
 use crate::graphics::frame::{
     object_draw::error::{ObjectDrawError, ObjectDrawSystemCreationError},
     system::error::{
         DrawPassExecuteError, FrameCreationError, FrameSystemCreationError, NextPassError,
     },
-    ui_draw::error::{UiDrawError, UiDrawSystemCreationError},
+    ui_draw::error::{TextureRegisterError, UiDrawError, UiDrawSystemCreationError},
 };
 
 /// Error that can happen when creating the [`Renderer`](super::Renderer) system.
 #[derive(Debug, Error)]
 pub enum RendererCreationError {
+    #[error("invalid application info: {0}")]
+    ApplicationInfo(#[from] ApplicationInfoError),
+
     #[error("instance creation failure: {0}")]
     InstanceCreation(#[from] InstanceCreationError),
 
@@ -36,6 +39,15 @@ pub enum RendererCreationError {
     #[error("no suitable physical device were found")]
     NoSuitablePhysicalDevice,
 
+    #[error("preferred physical device index {index} is out of range (found {count} devices)")]
+    PhysicalDeviceIndexOutOfRange { index: usize, count: usize },
+
+    #[error("requested device features are not supported: {0}")]
+    UnsupportedDeviceFeatures(String),
+
+    #[error("invalid queue request: {0}")]
+    QueueRequest(#[from] QueueRequestError),
+
     #[error("device creation failure: {0}")]
     DeviceCreation(#[from] DeviceCreationError),
 
@@ -58,6 +70,36 @@ pub enum RendererCreationError {
     UiDrawSystemCreation(#[from] UiDrawSystemCreationError),
 }
 
+/// Error that can happen when validating application info of the instance.
+#[derive(Debug, Error)]
+pub enum ApplicationInfoError {
+    #[error("application name contains NUL")]
+    ApplicationNameNul,
+
+    #[error("engine name contains NUL")]
+    EngineNameNul,
+}
+
+/// Error that can happen when validating queue requests.
+#[derive(Debug, Error)]
+pub enum QueueRequestError {
+    #[error("queue family {0} does not exist")]
+    UnknownFamily(u32),
+
+    #[error("queue family {0} was requested more than once")]
+    DuplicateFamily(u32),
+
+    #[error("{requested} queues were requested from family {family}, but it has {available}")]
+    InvalidQueueCount {
+        family: u32,
+        requested: usize,
+        available: usize,
+    },
+
+    #[error("queue priority {0} is out of range 0.0..=1.0")]
+    InvalidPriority(f32),
+}
+
 /// Error that can happen on descriptor set creation.
 #[derive(Debug, Error)]
 pub enum DescriptorSetCreationError {
@@ -65,9 +107,63 @@ pub enum DescriptorSetCreationError {
     Build(#[from] DescriptorSetError),
 }
 
+/// Error that can happen when querying subgroup properties of the physical device.
+#[derive(Debug, Error)]
+pub enum SubgroupPropertiesError {
+    #[error("subgroup properties can not be queried on device with Vulkan {api_version}")]
+    Unsupported { api_version: Version },
+}
+
+/// Error that can happen when querying driver properties of the physical device.
+#[derive(Debug, Error)]
+pub enum DriverPropertiesError {
+    #[error("driver properties can not be queried on device with Vulkan {api_version}")]
+    Unsupported { api_version: Version },
+}
+
+/// Error that can happen when querying UUID of the physical device.
+#[derive(Debug, Error)]
+pub enum DeviceUuidError {
+    #[error("device UUID can not be queried on device with Vulkan {api_version}")]
+    Unsupported { api_version: Version },
+}
+
+/// Error that can happen when setting HDR metadata of the swapchain.
+#[derive(Debug, Error)]
+pub enum HdrMetadataError {
+    #[error("VK_EXT_hdr_metadata extension is not enabled on the device")]
+    ExtensionNotEnabled,
+}
+
+/// Error that can happen when setting debug name of the Vulkan object.
+#[derive(Debug, Error)]
+pub enum ObjectNameError {
+    #[error("object name contains NUL")]
+    NameNul,
+
+    #[error("failed to set object name: {0}")]
+    OutOfMemory(#[from] OomError),
+}
+
 /// Error that can happen on resizing of [`Renderer`](super::Renderer) system.
 #[derive(Debug, Error)]
 pub enum ResizeError {
+    #[error("failed to get surface capabilities: {0}")]
+    SurfaceCapabilitiesRetrieve(#[from] CapabilitiesError),
+
+    #[error("swapchain recreation failure: {0}")]
+    SwapchainRecreation(#[from] SwapchainCreationError),
+}
+
+/// Error that can happen on changing present mode of [`Renderer`](super::Renderer) system.
+#[derive(Debug, Error)]
+pub enum PresentModeError {
+    #[error("present mode {0:?} is not supported by the surface")]
+    Unsupported(PresentMode),
+
+    #[error("failed to get surface capabilities: {0}")]
+    SurfaceCapabilitiesRetrieve(#[from] CapabilitiesError),
+
     #[error("swapchain recreation failure: {0}")]
     SwapchainRecreation(#[from] SwapchainCreationError),
 }
@@ -121,11 +217,51 @@ pub enum RenderError {
     Resize(#[from] ResizeError),
 }
 
+/// Severity of [`RenderError`], which helps to decide whether rendering can continue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorSeverity {
+    /// Rendering can continue after resources are recreated (e.g. swapchain is out of date).
+    Recoverable,
+
+    /// Rendering cannot continue (e.g. device is out of memory).
+    Fatal,
+
+    /// Severity of the error is not known.
+    Unknown,
+}
+
+impl RenderError {
+    /// Classifies this error by its severity.
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            Self::AcquireNextImage(error) => match error {
+                AcquireError::OutOfDate
+                | AcquireError::SurfaceLost
+                | AcquireError::DeviceLost
+                | AcquireError::Timeout
+                | AcquireError::FullScreenExclusiveLost => ErrorSeverity::Recoverable,
+                AcquireError::OomError(_) => ErrorSeverity::Fatal,
+                _ => ErrorSeverity::Unknown,
+            },
+            Self::SubmitQueue(error) => match error {
+                FlushError::OutOfDate
+                | FlushError::SurfaceLost
+                | FlushError::DeviceLost
+                | FlushError::Timeout
+                | FlushError::FullScreenExclusiveLost => ErrorSeverity::Recoverable,
+                FlushError::OomError(_) => ErrorSeverity::Fatal,
+                _ => ErrorSeverity::Unknown,
+            },
+            _ => ErrorSeverity::Fatal,
+        }
+    }
+}
+
 /// Error of registering an image for UI.
 #[derive(Debug, Error)]
 pub enum ImageRegisterError {
-    #[error("descriptor set for image creation failure: {0}")]
-    DescriptorSetCreation(#[from] DescriptorSetCreationError),
+    #[error("texture registration failure: {0}")]
+    TextureRegister(#[from] TextureRegisterError),
 
     #[error("image creation failure: {0}")]
     ImageCreation(#[from] ImageCreationError),
@@ -1,52 +1,383 @@
 //! Render utilities for graphics backend for game engine.
 
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::iter;
 use std::sync::Arc;
+use std::time::Duration;
 
-use egui::{ClippedMesh, Texture, TextureId};
+use egui::{ClippedMesh, Rect, Texture, TextureId};
 use image::RgbaImage;
 use vulkano::buffer::{BufferUsage, DeviceLocalBuffer};
 use vulkano::command_buffer::{
     AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer,
 };
-use vulkano::device::physical::PhysicalDevice;
+use vulkano::device::physical::{
+    DriverId, PhysicalDevice, Properties, QueueFamily, SubgroupFeatures,
+};
 use vulkano::device::{Device, DeviceExtensions, Features, Queue};
 use vulkano::format::Format;
 use vulkano::image::view::ImageView;
 use vulkano::image::{ImageDimensions, ImageUsage, ImmutableImage, MipmapsCount, SwapchainImage};
-use vulkano::instance::debug::{DebugCallback, MessageSeverity, MessageType};
+use vulkano::instance::debug::MessageType;
 use vulkano::instance::Instance;
-use vulkano::swapchain::{AcquireError, PresentMode, Surface, Swapchain};
+use vulkano::shader::ShaderStages;
+use vulkano::swapchain::{
+    AcquireError, ColorSpace, PresentMode, PresentRegion, RectangleLayer, Surface,
+    SurfaceTransform, Swapchain, SwapchainAcquireFuture,
+};
 use vulkano::sync::{FlushError, GpuFuture, SharingMode};
-use vulkano::{swapchain, sync};
+use vulkano::{swapchain, sync, VulkanObject};
 use vulkano_win::VkSurfaceBuild;
 use winit::dpi::LogicalSize;
 use winit::event_loop::EventLoop;
 use winit::window::{Window, WindowBuilder};
 
 pub use error::RendererCreationError;
-use error::{ImageRegisterError, RenderError, ResizeError, TransferCommandBufferCreationError};
+use error::{
+    DeviceUuidError, DriverPropertiesError, HdrMetadataError, ImageRegisterError, PresentModeError,
+    RenderError, ResizeError, SubgroupPropertiesError, TransferCommandBufferCreationError,
+};
+pub use report::DeviceReport;
+
+use queue::QueueRequest;
 
-use crate::config::Config;
+use crate::{
+    config::{Config, DebugMessengerSetting, DebugSeverity, DeviceSelector},
+    window::{PhysicalSize, WindowMetrics},
+};
 
 use super::{
     camera::CameraUBO,
+    debug_callback::DebugMessenger,
     frame::{
+        blur::{error::BlurBehindCreationError, BlurBehind},
         object_draw::ObjectDrawSystem,
         system::{FrameSystem, Pass},
-        ui_draw::UiDrawSystem,
+        ui_capture::{error::UiCaptureCreationError, UiCapture},
+        ui_draw::{TextureOptions, UiDrawSystem},
+        ui_supersample::{error::UiSupersamplerCreationError, UiSupersampler},
     },
     utils,
 };
 
 pub mod error;
+pub mod report;
+
+mod queue;
+mod tests;
+
+/// Driver properties of the physical device used by the renderer.
+#[derive(Clone, Debug)]
+pub struct DriverProperties {
+    id: DriverId,
+    name: String,
+    info: String,
+}
+
+impl DriverProperties {
+    /// Identifier of the driver (e.g. to apply known driver-specific workarounds).
+    pub fn driver_id(&self) -> DriverId {
+        self.id
+    }
+
+    /// Name of the driver.
+    pub fn driver_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Additional information about the driver (e.g. its version).
+    pub fn driver_info(&self) -> &str {
+        &self.info
+    }
+}
+
+/// Subgroup properties of the physical device used by the renderer.
+#[derive(Copy, Clone, Debug)]
+pub struct SubgroupProperties {
+    size: u32,
+    supported_stages: ShaderStages,
+    supported_operations: SubgroupFeatures,
+}
+
+impl SubgroupProperties {
+    /// Default number of invocations in each subgroup.
+    pub fn subgroup_size(&self) -> u32 {
+        self.size
+    }
+
+    /// Shader stages in which subgroup operations are supported.
+    pub fn supported_stages(&self) -> ShaderStages {
+        self.supported_stages
+    }
+
+    /// Subgroup operations which are supported.
+    pub fn supported_operations(&self) -> SubgroupFeatures {
+        self.supported_operations
+    }
+}
+
+/// Sparse resource support of the physical device used by the renderer.
+///
+/// Useful to decide if sparse resources (e.g. for virtual texturing
+/// or sparse-backed UI texture atlas) could be used.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseProperties {
+    /// Device supports binding of sparse resources (`sparseBinding` feature).
+    pub sparse_binding: bool,
+
+    /// Single-sample 2D images use the standard sparse image block shapes.
+    pub residency_standard_2d_block_shape: bool,
+
+    /// Multisample 2D images use the standard sparse image block shapes.
+    pub residency_standard_2d_multisample_block_shape: bool,
+
+    /// 3D images use the standard sparse image block shapes.
+    pub residency_standard_3d_block_shape: bool,
+
+    /// Mip levels which dimensions are not multiple of the sparse block size
+    /// may be placed in the mip tail.
+    pub residency_aligned_mip_size: bool,
+
+    /// Non-resident regions are reliably read as zero and writes to them are discarded.
+    pub residency_non_resident_strict: bool,
+}
+
+impl SparseProperties {
+    /// Collects sparse properties from properties and features of the physical device.
+    pub(crate) fn new(properties: &Properties, features: &Features) -> Self {
+        Self {
+            sparse_binding: features.sparse_binding,
+            residency_standard_2d_block_shape: properties.residency_standard2_d_block_shape,
+            residency_standard_2d_multisample_block_shape: properties
+                .residency_standard2_d_multisample_block_shape,
+            residency_standard_3d_block_shape: properties.residency_standard3_d_block_shape,
+            residency_aligned_mip_size: properties.residency_aligned_mip_size,
+            residency_non_resident_strict: properties.residency_non_resident_strict,
+        }
+    }
+}
+
+/// Conservative rasterization properties of the physical device used by the renderer.
+///
+/// Useful to decide if UI effects which rely on conservative rasterization
+/// (e.g. outlines of thin UI elements or coverage for picking) are feasible.
+///
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConservativeRasterProperties {
+    /// Size in pixels the generating primitive is increased by in overestimation mode.
+    pub primitive_overestimation_size: f32,
+
+    /// Max extra size in pixels which could be added to the generating primitive.
+    pub max_extra_primitive_overestimation_size: f32,
+
+    /// Granularity of extra overestimation size.
+    pub extra_primitive_overestimation_size_granularity: f32,
+
+    /// Device supports underestimation mode.
+    pub primitive_underestimation: bool,
+
+    /// Points and lines are rasterized conservatively as well as triangles.
+    pub conservative_point_and_line_rasterization: bool,
+
+    /// Degenerate triangles are rasterized in overestimation mode.
+    pub degenerate_triangles_rasterized: bool,
+
+    /// Degenerate lines are rasterized in overestimation mode.
+    pub degenerate_lines_rasterized: bool,
+
+    /// Fragment shaders could check if the fragment is fully covered by the primitive.
+    pub fully_covered_fragment_shader_input_variable: bool,
+
+    /// Conservative rasterization is applied after early fragment tests.
+    pub conservative_rasterization_post_depth_coverage: bool,
+}
+
+impl ConservativeRasterProperties {
+    /// Collects conservative rasterization properties from properties of the physical device.
+    ///
+    /// Returns `None` if `VK_EXT_conservative_rasterization` extension is not supported,
+    /// which leaves these properties unset.
+    ///
+    pub(crate) fn new(properties: &Properties) -> Option<Self> {
+        Some(Self {
+            primitive_overestimation_size: properties.primitive_overestimation_size?,
+            max_extra_primitive_overestimation_size: properties
+                .max_extra_primitive_overestimation_size?,
+            extra_primitive_overestimation_size_granularity: properties
+                .extra_primitive_overestimation_size_granularity?,
+            primitive_underestimation: properties.primitive_underestimation?,
+            conservative_point_and_line_rasterization: properties
+                .conservative_point_and_line_rasterization?,
+            degenerate_triangles_rasterized: properties.degenerate_triangles_rasterized?,
+            degenerate_lines_rasterized: properties.degenerate_lines_rasterized?,
+            fully_covered_fragment_shader_input_variable: properties
+                .fully_covered_fragment_shader_input_variable?,
+            conservative_rasterization_post_depth_coverage: properties
+                .conservative_rasterization_post_depth_coverage?,
+        })
+    }
+}
+
+/// Location of the physical device on PCI bus.
+///
+/// Useful to match the device with adapters of other APIs (e.g. CUDA or DXGI).
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PciBusInfo {
+    /// PCI domain of the device.
+    pub domain: u32,
+
+    /// PCI bus of the device.
+    pub bus: u32,
+
+    /// PCI device number of the device.
+    pub device: u32,
+
+    /// PCI function of the device.
+    pub function: u32,
+}
+
+impl PciBusInfo {
+    /// Collects PCI bus information from properties of the physical device.
+    ///
+    /// Returns `None` if `VK_EXT_pci_bus_info` extension is not supported,
+    /// which leaves these properties unset.
+    ///
+    pub(crate) fn new(properties: &Properties) -> Option<Self> {
+        Some(Self {
+            domain: properties.pci_domain?,
+            bus: properties.pci_bus?,
+            device: properties.pci_device?,
+            function: properties.pci_function?,
+        })
+    }
+}
+
+/// Mastering metadata of HDR content presented by the swapchain,
+/// which helps the display to tone-map it properly.
+///
+/// Chromaticities are CIE 1931 xy coordinates, luminance values are in nits.
+/// [`Default`] metadata describes typical HDR10 content: BT.2020 primaries,
+/// D65 white point, mastering display of 1000 nits maximum and 0.001 nits minimum luminance,
+/// maximum content light level of 1000 nits and maximum frame-average light level of 400 nits.
+///
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HdrMetadata {
+    /// Red primary of the mastering display.
+    pub display_primary_red: [f32; 2],
+
+    /// Green primary of the mastering display.
+    pub display_primary_green: [f32; 2],
+
+    /// Blue primary of the mastering display.
+    pub display_primary_blue: [f32; 2],
+
+    /// White point of the mastering display.
+    pub white_point: [f32; 2],
+
+    /// Maximum luminance of the mastering display.
+    pub max_luminance: f32,
+
+    /// Minimum luminance of the mastering display.
+    pub min_luminance: f32,
+
+    /// Maximum light level of any pixel of the content (MaxCLL).
+    pub max_content_light_level: f32,
+
+    /// Maximum average light level of any frame of the content (MaxFALL).
+    pub max_frame_average_light_level: f32,
+}
+
+impl Default for HdrMetadata {
+    fn default() -> Self {
+        Self {
+            display_primary_red: [0.708, 0.292],
+            display_primary_green: [0.170, 0.797],
+            display_primary_blue: [0.131, 0.046],
+            white_point: [0.3127, 0.3290],
+            max_luminance: 1000.0,
+            min_luminance: 0.001,
+            max_content_light_level: 1000.0,
+            max_frame_average_light_level: 400.0,
+        }
+    }
+}
+
+impl From<HdrMetadata> for ash::vk::HdrMetadataEXT {
+    fn from(metadata: HdrMetadata) -> Self {
+        let xy = |[x, y]: [f32; 2]| ash::vk::XYColorEXT { x, y };
+        Self {
+            display_primary_red: xy(metadata.display_primary_red),
+            display_primary_green: xy(metadata.display_primary_green),
+            display_primary_blue: xy(metadata.display_primary_blue),
+            white_point: xy(metadata.white_point),
+            max_luminance: metadata.max_luminance,
+            min_luminance: metadata.min_luminance,
+            max_content_light_level: metadata.max_content_light_level,
+            max_frame_average_light_level: metadata.max_frame_average_light_level,
+            ..Default::default()
+        }
+    }
+}
+
+/// Support of image format by the physical device with optimal tiling.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FormatSupport {
+    /// Images of the format can be sampled in shaders.
+    pub sampled: bool,
+
+    /// Images of the format can be used as color attachments.
+    pub color_attachment: bool,
+
+    /// Color attachments of the format support blending.
+    pub blendable: bool,
+
+    /// Images of the format can be both source and destination of blit commands.
+    pub blit: bool,
+
+    /// Images of the format can be used as storage images.
+    pub storage: bool,
+}
+
+impl FormatSupport {
+    /// Queries support of the format by the physical device.
+    pub(crate) fn query(physical_device: PhysicalDevice, format: Format) -> Self {
+        let features = format.properties(physical_device).optimal_tiling_features;
+        Self {
+            sampled: features.sampled_image,
+            color_attachment: features.color_attachment,
+            blendable: features.color_attachment_blend,
+            blit: features.blit_src && features.blit_dst,
+            storage: features.storage_image,
+        }
+    }
+}
+
+/// Outcome of acquiring next image of the swapchain.
+enum AcquireOutcome {
+    /// Image was acquired successfully.
+    Acquired {
+        image_index: usize,
+        suboptimal: bool,
+        future: SwapchainAcquireFuture<Window>,
+    },
+
+    /// No image became available until the timeout expired.
+    TimedOut,
+
+    /// Swapchain is out of date and must be recreated.
+    OutOfDate,
+}
 
 /// System that renders all game objects and UI.
 #[allow(dead_code)]
 pub struct Renderer {
     previous_frame_end: Option<Box<dyn GpuFuture + Send + Sync>>,
     recreate_swapchain: bool,
+    acquire_timeout: Option<Duration>,
     camera_ubo: CameraUBO,
 
     ui_draw_system: UiDrawSystem,
@@ -56,12 +387,16 @@ pub struct Renderer {
 
     swapchain_images: Vec<Arc<SwapchainImage<Window>>>,
     swapchain: Arc<Swapchain<Window>>,
+    color_space: ColorSpace,
     graphics_queue: Arc<Queue>,
     present_queue: Arc<Queue>,
     transfer_queue: Arc<Queue>,
+    compute_queue: Option<Arc<Queue>>,
+    queues: HashMap<u32, Vec<Arc<Queue>>>,
     device: Arc<Device>,
     surface: Arc<Surface<Window>>,
-    debug_callback: Option<DebugCallback>,
+    debug_callback: Option<DebugMessenger>,
+    instance_create_flags: ash::vk::InstanceCreateFlags,
     instance: Arc<Instance>,
 }
 
@@ -71,21 +406,31 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
     where
         T: 'static,
     {
+        utils::check_application_info(config)?;
         let instance = utils::create_instance(config)?;
         log::info!(
             "max version of Vulkan instance is {}",
             instance.max_api_version(),
         );
 
-        let debug_callback = config
-            .enable_validation()
-            .then(|| {
-                use super::debug_callback::create_debug_callback as new;
-                let debug_callback = new(&instance, MessageSeverity::all(), MessageType::all())?;
-                log::info!("debug callback was attached to the instance");
-                Result::<_, RendererCreationError>::Ok(debug_callback)
-            })
-            .transpose()?;
+        let debug_callback = match config.debug_messenger() {
+            DebugMessengerSetting::Enabled { .. }
+                if !instance.enabled_extensions().ext_debug_utils =>
+            {
+                log::warn!("debug callback was not attached: VK_EXT_debug_utils is not enabled");
+                None
+            }
+            DebugMessengerSetting::Enabled { min_severity } => {
+                let debug_callback =
+                    DebugMessenger::new(&instance, min_severity, MessageType::all())?;
+                log::info!(
+                    "debug callback was attached to the instance with {:?} severity",
+                    min_severity,
+                );
+                Some(debug_callback)
+            }
+            DebugMessengerSetting::Disabled => None,
+        };
 
         let surface = WindowBuilder::new()
             .with_title(config.name())
@@ -96,6 +441,12 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
 
         let physical_devices = PhysicalDevice::enumerate(&instance);
         log::info!("enumerated {} physical devices", physical_devices.len());
+        if let Some(&DeviceSelector::Index(index)) = config.preferred_device() {
+            let count = physical_devices.len();
+            if index >= count {
+                return Err(RendererCreationError::PhysicalDeviceIndexOutOfRange { index, count });
+            }
+        }
 
         let required_extensions = DeviceExtensions {
             khr_swapchain: true,
@@ -112,6 +463,7 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
             &surface,
             &required_extensions,
             &required_features,
+            config.preferred_device(),
         )
         .ok_or_else(|| RendererCreationError::NoSuitablePhysicalDevice)?;
         log::info!(
@@ -121,24 +473,70 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
             physical_device.api_version(),
         );
 
-        let (device, mut queues) = {
-            let priorities = 1.0;
-            let unique_queue_families = {
-                let unique_queue_families: HashSet<_> = [
-                    graphics_family.id(),
-                    present_family.unwrap_or(graphics_family).id(),
-                    transfer_family.unwrap_or(graphics_family).id(),
-                ]
-                .iter()
-                .cloned()
-                .collect();
-                unique_queue_families.into_iter().map(|family| {
-                    (
-                        physical_device.queue_family_by_id(family).unwrap(),
-                        priorities,
-                    )
-                })
-            };
+        let device_features = config.device_features();
+        let unsupported_features = utils::unsupported_features(physical_device, device_features);
+        if !unsupported_features.is_empty() {
+            return Err(RendererCreationError::UnsupportedDeviceFeatures(
+                unsupported_features.join(", "),
+            ));
+        }
+        let required_features = required_features.union(&utils::to_vk_features(device_features));
+        log::info!("enabled device features: {:?}", required_features);
+        let required_extensions =
+            required_extensions.union(&utils::feature_extensions(physical_device, device_features));
+        // Optional extension: damage regions are only a hint for the presentation engine.
+        let required_extensions = DeviceExtensions {
+            khr_incremental_present: physical_device
+                .supported_extensions()
+                .khr_incremental_present,
+            ..required_extensions
+        };
+        // Optional extension: HDR metadata is only a hint for tone-mapping of the display.
+        let required_extensions = DeviceExtensions {
+            ext_hdr_metadata: physical_device.supported_extensions().ext_hdr_metadata,
+            ..required_extensions
+        };
+
+        let queue_priorities = config.queue_priorities();
+        let compute_family = queue_priorities.async_compute.and_then(|priority| {
+            let family = physical_device
+                .queue_families()
+                .find(|family| family.supports_compute() && !family.supports_graphics());
+            if family.is_none() {
+                log::warn!("async compute queue was requested, but no compute-only family found");
+            }
+            family.map(|family| (family, priority))
+        });
+        let queue_requests = {
+            let mut queue_requests = vec![QueueRequest::new(
+                graphics_family.id(),
+                queue_priorities.graphics,
+            )];
+            let other_families = [present_family, transfer_family]
+                .into_iter()
+                .flatten()
+                .map(|family| (family, 1.0))
+                .chain(compute_family);
+            for (family, priority) in other_families {
+                if queue_requests
+                    .iter()
+                    .all(|request| request.family != family.id())
+                {
+                    queue_requests.push(QueueRequest::new(family.id(), priority));
+                }
+            }
+            queue_requests
+        };
+
+        let (device, queues) = {
+            let queue_create_infos = queue::queue_create_infos(&queue_requests, |family| {
+                let family = physical_device.queue_family_by_id(family)?;
+                Some(family.queues_count())
+            })?;
+            let queue_create_infos = queue_create_infos.into_iter().map(|(family, priority)| {
+                let family = physical_device.queue_family_by_id(family).unwrap();
+                (family, priority)
+            });
             let required_extensions = physical_device
                 .required_extensions()
                 .union(&required_extensions);
@@ -146,14 +544,38 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
                 physical_device,
                 &required_features,
                 &required_extensions,
-                unique_queue_families,
+                queue_create_infos,
             )?
         };
-        let graphics_queue = queues.next().unwrap();
-        let present_queue = queues.next().unwrap_or_else(|| graphics_queue.clone());
-        let transfer_queue = queues.next().unwrap_or_else(|| graphics_queue.clone());
+        let queues = queues.fold(HashMap::<_, Vec<_>>::new(), |mut queues, queue| {
+            queues.entry(queue.family().id()).or_default().push(queue);
+            queues
+        });
+        let family_queue = |family: Option<QueueFamily>| {
+            let family = family.unwrap_or(graphics_family);
+            queues[&family.id()][0].clone()
+        };
+        let graphics_queue = family_queue(Some(graphics_family));
+        let present_queue = family_queue(present_family);
+        let transfer_queue = family_queue(transfer_family);
+        let compute_queue = compute_family.map(|(family, _)| family_queue(Some(family)));
+        // Queues could be shared, so name of the graphics queue is set last to take precedence.
+        let queue_names = [
+            (Some(&transfer_queue), "transfer queue"),
+            (compute_queue.as_ref(), "async compute queue"),
+            (Some(&present_queue), "present queue"),
+            (Some(&graphics_queue), "graphics queue"),
+        ];
+        let queue_names = queue_names
+            .into_iter()
+            .filter_map(|(queue, name)| Some((queue?, name)));
+        for (queue, name) in queue_names {
+            if let Err(error) = super::set_object_name(&**queue, name) {
+                log::warn!("failed to set name of {}: {}", name, error);
+            }
+        }
 
-        let (swapchain, swapchain_images) = {
+        let (swapchain, swapchain_images, color_space) = {
             let capabilities = surface.capabilities(physical_device)?;
             let (format, color_space) = utils::suitable_image_format(&capabilities);
             let present_mode = capabilities
@@ -164,15 +586,11 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
             let dimensions = if let Some(current_extent) = capabilities.current_extent {
                 current_extent
             } else {
-                let window_size = surface.window().inner_size();
-                let min_width = capabilities.min_image_extent[0];
-                let max_width = capabilities.max_image_extent[0];
-                let min_height = capabilities.min_image_extent[1];
-                let max_height = capabilities.max_image_extent[1];
-                [
-                    window_size.width.clamp(min_width, max_width),
-                    window_size.height.clamp(min_height, max_height),
-                ]
+                // Swapchain extent is in physical pixels, as well as inner size of the window.
+                let window_size = PhysicalSize::from(surface.window().inner_size());
+                let min_extent = capabilities.min_image_extent.into();
+                let max_extent = capabilities.max_image_extent.into();
+                window_size.clamp(min_extent, max_extent).into()
             };
             let image_count = {
                 let image_count = capabilities.min_image_count + 1;
@@ -192,7 +610,7 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
                 })
                 .flatten()
                 .unwrap_or_else(|| SharingMode::from(&graphics_queue));
-            Swapchain::start(device.clone(), surface.clone())
+            let (swapchain, swapchain_images) = Swapchain::start(device.clone(), surface.clone())
                 .format(format)
                 .color_space(color_space)
                 .present_mode(present_mode)
@@ -201,7 +619,8 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
                 .transform(capabilities.current_transform)
                 .sharing_mode(sharing_mode)
                 .usage(ImageUsage::color_attachment())
-                .build()?
+                .build()?;
+            (swapchain, swapchain_images, color_space)
         };
 
         let uniform_buffers = swapchain_images
@@ -220,19 +639,28 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
         let object_draw_system =
             ObjectDrawSystem::new(graphics_queue.clone(), frame_system.object_subpass())?;
 
-        let ui_draw_system = UiDrawSystem::new(graphics_queue.clone(), frame_system.ui_subpass())?;
+        let mut ui_draw_system = UiDrawSystem::new(
+            graphics_queue.clone(),
+            frame_system.ui_subpass(),
+            swapchain_images.len(),
+        )?;
+        ui_draw_system.set_rotation(swapchain.transform().into());
 
         let previous_frame_end = Some(Box::new(sync::now(device.clone())) as Box<_>);
         Ok(Self {
             instance,
+            instance_create_flags: utils::INSTANCE_CREATE_FLAGS,
             debug_callback,
             surface,
             device,
             graphics_queue,
             present_queue,
             transfer_queue,
+            compute_queue,
+            queues,
             swapchain,
             swapchain_images,
+            color_space,
             uniform_buffers,
             frame_system,
             object_draw_system,
@@ -240,6 +668,7 @@ pub fn new<T>(config: &Config, event_loop: &EventLoop<T>) -> Result<Self, Render
             camera_ubo: CameraUBO::default(),
             previous_frame_end,
             recreate_swapchain: false,
+            acquire_timeout: None,
         })
     }
 
@@ -250,17 +679,382 @@ pub fn window(&self) -> &Window {
 
     /// Resize the underlying window and update Vulkan objects.
     pub fn resize(&mut self) -> Result<(), ResizeError> {
-        let dimensions = self.window().inner_size().into();
+        // Current transform of the surface could change along with its size (e.g. on rotation).
+        let capabilities = self.surface.capabilities(self.device.physical_device())?;
+        let transform = capabilities.current_transform;
+        let dimensions = self::swapchain_dimensions(
+            capabilities.current_extent,
+            self.window().inner_size().into(),
+            transform,
+        );
 
-        let (swapchain, swapchain_images) =
-            self.swapchain.recreate().dimensions(dimensions).build()?;
+        let (swapchain, swapchain_images) = self
+            .swapchain
+            .recreate()
+            .dimensions(dimensions)
+            .transform(transform)
+            .build()?;
         self.swapchain = swapchain;
         self.swapchain_images = swapchain_images;
+        self.ui_draw_system.set_rotation(transform.into());
 
         self.recreate_swapchain = false;
         Ok(())
     }
 
+    /// Sets present mode of the swapchain (e.g. to toggle vertical synchronization).
+    ///
+    /// Present mode cannot be changed without recreation of the swapchain
+    /// (`VK_EXT_swapchain_maintenance1` extension is not supported by the backend),
+    /// so the swapchain is recreated if new present mode differs from the current one.
+    /// Returns if the swapchain was recreated.
+    ///
+    pub fn set_present_mode(
+        &mut self,
+        present_mode: PresentMode,
+    ) -> Result<bool, PresentModeError> {
+        if self.swapchain.present_mode() == present_mode {
+            return Ok(false);
+        }
+        let capabilities = self.surface.capabilities(self.device.physical_device())?;
+        if !capabilities.present_modes.supports(present_mode) {
+            return Err(PresentModeError::Unsupported(present_mode));
+        }
+
+        let dimensions = self.window().inner_size().into();
+        let (swapchain, swapchain_images) = self
+            .swapchain
+            .recreate()
+            .dimensions(dimensions)
+            .present_mode(present_mode)
+            .build()?;
+        self.swapchain = swapchain;
+        self.swapchain_images = swapchain_images;
+        log::info!(
+            "swapchain was recreated with present mode {:?}",
+            present_mode
+        );
+        Ok(true)
+    }
+
+    /// Sets minimal severity of messages which are reported by debug messenger.
+    ///
+    /// Does nothing if debug messenger was not installed.
+    ///
+    pub fn set_debug_severity(&self, min_severity: DebugSeverity) {
+        if let Some(debug_callback) = &self.debug_callback {
+            debug_callback.set_min_severity(min_severity);
+        }
+    }
+
+    /// Version of Vulkan API negotiated with the driver on instance creation.
+    pub fn api_version(&self) -> vulkano::Version {
+        self.instance.api_version()
+    }
+
+    /// Instance of Vulkan used by the renderer.
+    ///
+    /// Useful for integration with third-party Vulkan libraries,
+    /// which could retrieve function pointers of the instance from it.
+    ///
+    pub fn instance(&self) -> &Arc<Instance> {
+        &self.instance
+    }
+
+    /// Flags which were used on creation of the instance.
+    ///
+    /// Device creation code could check them instead of re-deriving platform state
+    /// (e.g. whether portability subset devices were enumerated).
+    ///
+    pub fn instance_create_flags(&self) -> ash::vk::InstanceCreateFlags {
+        self.instance_create_flags
+    }
+
+    /// Logical device used by the renderer.
+    ///
+    /// Useful for integration with third-party Vulkan libraries,
+    /// which could retrieve function pointers of the device from it.
+    ///
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Raw handle of the instance of Vulkan used by the renderer (e.g. for FFI).
+    pub fn raw_instance(&self) -> <Instance as VulkanObject>::Object {
+        self.instance.internal_object()
+    }
+
+    /// Raw handle of the logical device used by the renderer (e.g. for FFI).
+    pub fn raw_device(&self) -> <Device as VulkanObject>::Object {
+        self.device.internal_object()
+    }
+
+    /// Checks if `vkGetPhysicalDeviceProperties2` family of queries is usable.
+    ///
+    /// These queries are core since Vulkan 1.1,
+    /// otherwise `VK_KHR_get_physical_device_properties2` extension must be enabled.
+    ///
+    pub fn properties2_supported(&self) -> bool {
+        self.instance.api_version() >= vulkano::Version::V1_1
+            || self
+                .instance
+                .enabled_extensions()
+                .khr_get_physical_device_properties2
+    }
+
+    /// Features which were actually enabled on the device.
+    pub fn enabled_features(&self) -> &Features {
+        self.device.enabled_features()
+    }
+
+    /// All queues which were created from queue family with given index.
+    pub fn queues(&self, family: u32) -> &[Arc<Queue>] {
+        self.queues.get(&family).map_or(&[], Vec::as_slice)
+    }
+
+    /// Async compute queue, if it was requested and created.
+    pub fn compute_queue(&self) -> Option<&Arc<Queue>> {
+        self.compute_queue.as_ref()
+    }
+
+    /// Collects information about the physical device used by the renderer.
+    pub fn device_report(&self) -> DeviceReport {
+        DeviceReport::new(self.device.physical_device(), self.instance_create_flags)
+    }
+
+    /// Support of image format by the physical device used by the renderer.
+    pub fn format_support(&self, format: Format) -> FormatSupport {
+        FormatSupport::query(self.device.physical_device(), format)
+    }
+
+    /// First depth format supported as depth attachment by the physical device
+    /// used by the renderer, trying `D32_SFLOAT` first.
+    ///
+    /// If stencil is wanted, only formats with stencil component are considered.
+    ///
+    pub fn find_depth_format(&self, want_stencil: bool) -> Option<Format> {
+        utils::find_depth_format(self.device.physical_device(), want_stencil)
+    }
+
+    /// Count of images of the swapchain which were actually created.
+    pub fn swapchain_image_count(&self) -> u32 {
+        self.swapchain.num_images()
+    }
+
+    /// Format of images of the swapchain.
+    pub fn swapchain_format(&self) -> Format {
+        self.swapchain.format()
+    }
+
+    /// Color space of images of the swapchain.
+    pub fn swapchain_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Sets mastering metadata of HDR content presented by the swapchain.
+    ///
+    /// Requires `VK_EXT_hdr_metadata` device extension, which is enabled if supported.
+    /// Metadata is meaningful only for HDR color spaces of the swapchain
+    /// (e.g. `Hdr10St2084`) and should be set again after the swapchain is recreated.
+    ///
+    pub fn set_hdr_metadata(&self, metadata: HdrMetadata) -> Result<(), HdrMetadataError> {
+        if !self.device.enabled_extensions().ext_hdr_metadata {
+            return Err(HdrMetadataError::ExtensionNotEnabled);
+        }
+        let metadata = ash::vk::HdrMetadataEXT::from(metadata);
+        let swapchain = self.swapchain.internal_object();
+        // SAFETY: extension is enabled, swapchain is owned by the device.
+        unsafe {
+            self.device.fns().ext_hdr_metadata.set_hdr_metadata_ext(
+                self.device.internal_object(),
+                1,
+                &swapchain,
+                &metadata,
+            );
+        }
+        Ok(())
+    }
+
+    /// Pre-transform of the swapchain, which is the current transform of the surface.
+    ///
+    /// Images are presented with this transform applied by the presentation engine,
+    /// so no additional composition pass is needed on rotated displays.
+    /// UI is rotated to compensate this transform automatically,
+    /// but projection of the scene is not pre-rotated, so the scene is drawn rotated
+    /// by this transform unless the caller rotates its camera.
+    ///
+    pub fn pre_transform(&self) -> SurfaceTransform {
+        self.swapchain.transform()
+    }
+
+    /// Present mode of the swapchain.
+    pub fn present_mode(&self) -> PresentMode {
+        self.swapchain.present_mode()
+    }
+
+    /// Timeout of acquiring next image of the swapchain; `None` means infinite timeout.
+    pub fn acquire_timeout(&self) -> Option<Duration> {
+        self.acquire_timeout
+    }
+
+    /// Sets timeout of acquiring next image of the swapchain.
+    ///
+    /// With finite timeout, stuck present engine (e.g. hanging compositor) is detected:
+    /// if no image became available in time, the frame is skipped instead of blocking forever.
+    ///
+    pub fn set_acquire_timeout(&mut self, timeout: Option<Duration>) {
+        self.acquire_timeout = timeout;
+    }
+
+    /// Acquires next image of the swapchain, waiting for it no longer than given timeout.
+    ///
+    /// Expired timeout and out of date swapchain are non-fatal and reported as distinct outcomes.
+    ///
+    fn acquire_next_image_timeout(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<AcquireOutcome, AcquireError> {
+        match swapchain::acquire_next_image(self.swapchain.clone(), timeout) {
+            Ok((image_index, suboptimal, future)) => Ok(AcquireOutcome::Acquired {
+                image_index,
+                suboptimal,
+                future,
+            }),
+            Err(AcquireError::Timeout) => Ok(AcquireOutcome::TimedOut),
+            Err(AcquireError::OutOfDate) => Ok(AcquireOutcome::OutOfDate),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Extent of images of the swapchain, which could differ from size of the window.
+    pub fn swapchain_extent(&self) -> PhysicalSize {
+        self.swapchain.dimensions().into()
+    }
+
+    /// Subgroup properties of the physical device used by the renderer.
+    ///
+    /// These properties are queried through `vkGetPhysicalDeviceProperties2`,
+    /// so physical device must support Vulkan 1.1 or higher.
+    ///
+    pub fn subgroup_properties(&self) -> Result<SubgroupProperties, SubgroupPropertiesError> {
+        let physical_device = self.device.physical_device();
+        let properties = physical_device.properties();
+        let unsupported = || SubgroupPropertiesError::Unsupported {
+            api_version: physical_device.api_version(),
+        };
+        Ok(SubgroupProperties {
+            size: properties.subgroup_size.ok_or_else(unsupported)?,
+            supported_stages: properties
+                .subgroup_supported_stages
+                .ok_or_else(unsupported)?,
+            supported_operations: properties
+                .subgroup_supported_operations
+                .ok_or_else(unsupported)?,
+        })
+    }
+
+    /// Driver properties of the physical device used by the renderer.
+    ///
+    /// These properties are queried through `vkGetPhysicalDeviceProperties2`,
+    /// so physical device must support Vulkan 1.2 or `VK_KHR_driver_properties` extension.
+    ///
+    pub fn driver_properties(&self) -> Result<DriverProperties, DriverPropertiesError> {
+        let physical_device = self.device.physical_device();
+        let properties = physical_device.properties();
+        let unsupported = || DriverPropertiesError::Unsupported {
+            api_version: physical_device.api_version(),
+        };
+        Ok(DriverProperties {
+            id: properties.driver_id.ok_or_else(unsupported)?,
+            name: properties.driver_name.clone().ok_or_else(unsupported)?,
+            info: properties.driver_info.clone().ok_or_else(unsupported)?,
+        })
+    }
+
+    /// Count of nanoseconds per tick of GPU timestamps written by queries
+    /// of the physical device used by the renderer.
+    ///
+    /// Returns `None` if timestamps are not supported on all graphics and compute queues.
+    /// Timestamp queries can be issued with [`vulkano::query::QueryPool`].
+    ///
+    pub fn timestamp_period(&self) -> Option<f32> {
+        self::timestamp_period(self.device.physical_device().properties())
+    }
+
+    /// Sparse resource support of the physical device used by the renderer.
+    pub fn sparse_properties(&self) -> SparseProperties {
+        let physical_device = self.device.physical_device();
+        SparseProperties::new(
+            physical_device.properties(),
+            physical_device.supported_features(),
+        )
+    }
+
+    /// Conservative rasterization properties of the physical device used by the renderer.
+    ///
+    /// These properties are queried through `vkGetPhysicalDeviceProperties2`
+    /// if `VK_EXT_conservative_rasterization` extension is supported by the physical device.
+    /// Returns `None` otherwise.
+    ///
+    pub fn conservative_raster_properties(&self) -> Option<ConservativeRasterProperties> {
+        ConservativeRasterProperties::new(self.device.physical_device().properties())
+    }
+
+    /// Universally unique identifier of the physical device used by the renderer.
+    ///
+    /// Unlike the index of the device in enumeration order, UUID is stable across runs,
+    /// so it could be persisted (e.g. as the last used device) or matched with other APIs.
+    /// It is queried through `vkGetPhysicalDeviceProperties2`,
+    /// so physical device must support Vulkan 1.1.
+    ///
+    pub fn device_uuid(&self) -> Result<[u8; 16], DeviceUuidError> {
+        let physical_device = self.device.physical_device();
+        physical_device
+            .properties()
+            .device_uuid
+            .ok_or_else(|| DeviceUuidError::Unsupported {
+                api_version: physical_device.api_version(),
+            })
+    }
+
+    /// Location on PCI bus of the physical device used by the renderer.
+    ///
+    /// These properties are queried through `vkGetPhysicalDeviceProperties2`
+    /// if `VK_EXT_pci_bus_info` extension is supported by the physical device.
+    /// Returns `None` otherwise.
+    ///
+    pub fn pci_bus_info(&self) -> Option<PciBusInfo> {
+        PciBusInfo::new(self.device.physical_device().properties())
+    }
+
+    /// Creates helper that blurs regions of images behind UI panels.
+    pub fn create_blur_behind(&self) -> Result<BlurBehind, BlurBehindCreationError> {
+        BlurBehind::new(self.graphics_queue.clone())
+    }
+
+    /// Creates system that renders UI into an offscreen image (e.g. for screenshots).
+    pub fn create_ui_capture(&self) -> Result<UiCapture, UiCaptureCreationError> {
+        UiCapture::new(self.graphics_queue.clone())
+    }
+
+    /// Creates system that renders UI at higher internal resolution
+    /// with given supersample factor for sharper small fonts.
+    pub fn create_ui_supersampler(
+        &self,
+        factor: f32,
+    ) -> Result<UiSupersampler, UiSupersamplerCreationError> {
+        UiSupersampler::new(
+            self.graphics_queue.clone(),
+            factor,
+            self.swapchain_images.len(),
+        )
+    }
+
+    /// System that renders UI of the underlying window.
+    pub fn ui_draw_system(&mut self) -> &mut UiDrawSystem {
+        &mut self.ui_draw_system
+    }
+
     pub fn set_camera_ubo(&mut self, ubo: CameraUBO) {
         self.camera_ubo = ubo;
     }
@@ -285,6 +1079,7 @@ fn transfer_cb(
     pub fn register_ui_image(
         &mut self,
         image: &RgbaImage,
+        options: TextureOptions,
     ) -> Result<TextureId, ImageRegisterError> {
         let pixels: Vec<_> = image.pixels().flat_map(|p| p.0).collect();
         let (image, future) = ImmutableImage::from_iter(
@@ -300,13 +1095,33 @@ pub fn register_ui_image(
         )?;
         future.flush()?;
         let image_view = ImageView::new(image)?;
-        Ok(self.ui_draw_system.register_texture(image_view)?)
+        let (texture_id, evicted) = self.ui_draw_system.register_texture(image_view, options)?;
+        if let Some(evicted) = evicted {
+            log::debug!("UI texture {:?} was evicted", evicted);
+        }
+        Ok(texture_id)
     }
 
     /// Render new frame into the underlying window.
     pub fn render(
+        &mut self,
+        ui: Option<(Vec<ClippedMesh>, Arc<Texture>)>,
+    ) -> Result<(), RenderError> {
+        self.render_with_damage(ui, None)
+    }
+
+    /// Render new frame into the underlying window, presenting only damaged regions
+    /// (rectangles in logical points which changed since the previous frame).
+    ///
+    /// Damage regions are passed to the presentation engine with `VK_KHR_incremental_present`,
+    /// so the compositor could update only these regions, which saves power on mobile devices.
+    /// The whole frame is still rendered. If the extension is not enabled on the device
+    /// or damage is `None`, the whole image is presented.
+    ///
+    pub fn render_with_damage(
         &mut self,
         mut ui: Option<(Vec<ClippedMesh>, Arc<Texture>)>,
+        damage: Option<&[Rect]>,
     ) -> Result<(), RenderError> {
         self.previous_frame_end.as_mut().unwrap().cleanup_finished();
         if self.recreate_swapchain {
@@ -314,24 +1129,45 @@ pub fn render(
         }
 
         let (image_index, suboptimal, acquire_future) =
-            match swapchain::acquire_next_image(self.swapchain.clone(), None) {
-                Ok(r) => r,
-                Err(AcquireError::OutOfDate) => {
+            match self.acquire_next_image_timeout(self.acquire_timeout)? {
+                AcquireOutcome::Acquired {
+                    image_index,
+                    suboptimal,
+                    future,
+                } => (image_index, suboptimal, future),
+                AcquireOutcome::TimedOut => {
+                    log::warn!(
+                        "no swapchain image was acquired in {:?}, frame is skipped",
+                        self.acquire_timeout,
+                    );
+                    return Ok(());
+                }
+                AcquireOutcome::OutOfDate => {
                     self.recreate_swapchain = true;
                     return Ok(());
                 }
-                Err(err) => return Err(RenderError::AcquireNextImage(err)),
             };
         self.recreate_swapchain = suboptimal;
 
         let transfer_command_buffer = self.transfer_cb(image_index)?;
         let previous_frame_end = self.previous_frame_end.take().unwrap();
-        let before_future = previous_frame_end
-            .join(acquire_future)
-            .then_execute(self.transfer_queue.clone(), transfer_command_buffer)?
-            .then_signal_semaphore();
+        let mut before_future: Box<dyn GpuFuture + Send + Sync> = Box::new(
+            previous_frame_end
+                .join(acquire_future)
+                .then_execute(self.transfer_queue.clone(), transfer_command_buffer)?
+                .then_signal_semaphore(),
+        );
+        // Timestamp queries of UI draw could be reset only outside of the render pass.
+        if ui.is_some() {
+            if let Some(reset_command_buffer) = self.ui_draw_system.reset_gpu_timing(image_index)? {
+                before_future = Box::new(
+                    before_future
+                        .then_execute(self.graphics_queue.clone(), reset_command_buffer)?,
+                );
+            }
+        }
 
-        let scale_factor = self.window().scale_factor() as f32;
+        let scale_factor = WindowMetrics::from_window(self.window()).scale_factor;
         let graphics_future = {
             let mut frame = self
                 .frame_system
@@ -353,6 +1189,8 @@ pub fn render(
                                 scale_factor,
                                 meshes,
                                 texture,
+                                image_index,
+                                None,
                             )?;
                             ui_pass.execute(command_buffer)?;
                         }
@@ -365,13 +1203,23 @@ pub fn render(
             graphics_future
         };
 
-        let future = graphics_future
-            .then_swapchain_present(
+        let present_region = damage
+            .filter(|_| self.device.enabled_extensions().khr_incremental_present)
+            .map(|damage| self::present_region(damage, scale_factor, self.swapchain_extent()));
+        let present_future = match present_region {
+            Some(present_region) => graphics_future.then_swapchain_present_incremental(
                 self.present_queue.clone(),
                 self.swapchain.clone(),
                 image_index,
-            )
-            .then_signal_fence_and_flush();
+                present_region,
+            ),
+            None => graphics_future.then_swapchain_present(
+                self.present_queue.clone(),
+                self.swapchain.clone(),
+                image_index,
+            ),
+        };
+        let future = present_future.then_signal_fence_and_flush();
         match future {
             Ok(future) => {
                 self.previous_frame_end = Some(Box::new(future));
@@ -389,3 +1237,58 @@ pub fn render(
         }
     }
 }
+
+/// Retrieves timestamp period from properties of the physical device
+/// if timestamps are supported on all graphics and compute queues.
+fn timestamp_period(properties: &Properties) -> Option<f32> {
+    properties
+        .timestamp_compute_and_graphics
+        .then(|| properties.timestamp_period)
+}
+
+/// Selects dimensions of the swapchain from the current extent of the surface
+/// and the inner size of the window in physical pixels.
+///
+/// Swapchain extent is in orientation of the display before pre-transform,
+/// so size of the window is swapped if the display is rotated by 90 or 270 degrees.
+///
+fn swapchain_dimensions(
+    current_extent: Option<[u32; 2]>,
+    window_size: [u32; 2],
+    transform: SurfaceTransform,
+) -> [u32; 2] {
+    if let Some(current_extent) = current_extent {
+        return current_extent;
+    }
+    let [width, height] = window_size;
+    match transform {
+        SurfaceTransform::Rotate90
+        | SurfaceTransform::Rotate270
+        | SurfaceTransform::HorizontalMirrorRotate90
+        | SurfaceTransform::HorizontalMirrorRotate270 => [height, width],
+        _ => [width, height],
+    }
+}
+
+/// Converts damage rectangles in logical points into present region of the swapchain image.
+///
+/// Rectangles are rounded outwards to whole pixels and clipped by the extent of the image;
+/// rectangles outside of the image are skipped.
+///
+fn present_region(damage: &[Rect], scale_factor: f32, extent: PhysicalSize) -> PresentRegion {
+    let rectangles = damage
+        .iter()
+        .filter_map(|rect| {
+            let min_x = (rect.min.x * scale_factor).floor().max(0.0) as u32;
+            let min_y = (rect.min.y * scale_factor).floor().max(0.0) as u32;
+            let max_x = ((rect.max.x * scale_factor).ceil().max(0.0) as u32).min(extent.width);
+            let max_y = ((rect.max.y * scale_factor).ceil().max(0.0) as u32).min(extent.height);
+            (min_x < max_x && min_y < max_y).then(|| RectangleLayer {
+                offset: [min_x as i32, min_y as i32],
+                extent: [max_x - min_x, max_y - min_y],
+                layer: 0,
+            })
+        })
+        .collect();
+    PresentRegion { rectangles }
+}
@@ -0,0 +1,71 @@
+//! Queue creation utilities for graphics backend for game engine.
+
+use super::error::QueueRequestError;
+
+/// Request of queues from one queue family on device creation.
+///
+/// Requests are built by the renderer from [queue priorities](crate::config::QueuePriorities)
+/// of the configuration, so they are not exposed to the user.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueRequest {
+    /// Index of the queue family.
+    pub family: u32,
+
+    /// Priorities of queues in range `0.0..=1.0`, one for each queue requested.
+    pub priorities: Vec<f32>,
+}
+
+impl QueueRequest {
+    /// Creates new request of one queue with given priority.
+    pub fn new(family: u32, priority: f32) -> Self {
+        Self {
+            family,
+            priorities: vec![priority],
+        }
+    }
+}
+
+/// Validates queue requests and flattens them into pairs of family index and queue priority.
+///
+/// Count of queues of the family is retrieved by provided function,
+/// which returns `None` if there is no family with such index.
+///
+pub fn queue_create_infos(
+    requests: &[QueueRequest],
+    queues_count: impl Fn(u32) -> Option<usize>,
+) -> Result<Vec<(u32, f32)>, QueueRequestError> {
+    let mut create_infos = Vec::new();
+    for (position, request) in requests.iter().enumerate() {
+        let family = request.family;
+        if requests[..position]
+            .iter()
+            .any(|other| other.family == family)
+        {
+            return Err(QueueRequestError::DuplicateFamily(family));
+        }
+        let available = queues_count(family).ok_or(QueueRequestError::UnknownFamily(family))?;
+        let requested = request.priorities.len();
+        if requested == 0 || requested > available {
+            return Err(QueueRequestError::InvalidQueueCount {
+                family,
+                requested,
+                available,
+            });
+        }
+        if let Some(&priority) = request
+            .priorities
+            .iter()
+            .find(|priority| !(0.0..=1.0).contains(*priority))
+        {
+            return Err(QueueRequestError::InvalidPriority(priority));
+        }
+        create_infos.extend(
+            request
+                .priorities
+                .iter()
+                .map(|&priority| (family, priority)),
+        );
+    }
+    Ok(create_infos)
+}
@@ -0,0 +1,241 @@
+//! Physical device report which can be attached to bug reports.
+
+use std::ffi::CString;
+use std::fmt;
+
+use vulkano::device::physical::PhysicalDevice;
+
+use super::{ConservativeRasterProperties, PciBusInfo};
+
+/// Information about physical device used by the renderer.
+///
+/// Can be printed in a readable form or serialized (with `serde` feature enabled).
+///
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceReport {
+    /// Name of the device.
+    pub name: String,
+
+    /// Type of the device (e.g. discrete or integrated GPU).
+    pub device_type: String,
+
+    /// Vendor identifier of the device.
+    pub vendor_id: u32,
+
+    /// Device identifier of the vendor.
+    pub device_id: u32,
+
+    /// Vendor-specific version of the driver.
+    pub driver_version: u32,
+
+    /// Name of the driver, if driver properties are supported by the device.
+    pub driver_name: Option<String>,
+
+    /// Version of Vulkan API supported by the device.
+    pub api_version: String,
+
+    /// Universally unique identifier of the device, if it could be queried.
+    pub device_uuid: Option<[u8; 16]>,
+
+    /// Location of the device on PCI bus, if it could be queried.
+    pub pci_bus_info: Option<PciBusInfo>,
+
+    /// Raw flags which were used on creation of the instance.
+    pub instance_create_flags: u32,
+
+    /// Key limits of the device.
+    pub limits: DeviceLimitsReport,
+
+    /// Conservative rasterization properties, if supported by the device.
+    pub conservative_rasterization: Option<ConservativeRasterProperties>,
+
+    /// Names of supported device extensions.
+    pub extensions: Vec<String>,
+
+    /// Queue families of the device.
+    pub queue_families: Vec<QueueFamilyReport>,
+}
+
+/// Key limits of physical device.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceLimitsReport {
+    pub max_image_dimension_2d: u32,
+    pub max_push_constants_size: u32,
+    pub max_bound_descriptor_sets: u32,
+    pub max_per_stage_descriptor_samplers: u32,
+    pub max_compute_work_group_invocations: u32,
+    pub max_sampler_anisotropy: f32,
+}
+
+/// Information about queue family of physical device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueueFamilyReport {
+    pub id: u32,
+    pub queues_count: usize,
+    pub graphics: bool,
+    pub compute: bool,
+    pub transfer: bool,
+}
+
+impl DeviceReport {
+    /// Collects information about physical device.
+    pub(crate) fn new(
+        physical_device: PhysicalDevice,
+        instance_create_flags: ash::vk::InstanceCreateFlags,
+    ) -> Self {
+        let properties = physical_device.properties();
+        let limits = DeviceLimitsReport {
+            max_image_dimension_2d: properties.max_image_dimension2_d,
+            max_push_constants_size: properties.max_push_constants_size,
+            max_bound_descriptor_sets: properties.max_bound_descriptor_sets,
+            max_per_stage_descriptor_samplers: properties.max_per_stage_descriptor_samplers,
+            max_compute_work_group_invocations: properties.max_compute_work_group_invocations,
+            max_sampler_anisotropy: properties.max_sampler_anisotropy,
+        };
+        let extensions = Vec::<CString>::from(physical_device.supported_extensions())
+            .into_iter()
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        let queue_families = physical_device
+            .queue_families()
+            .map(|family| QueueFamilyReport {
+                id: family.id(),
+                queues_count: family.queues_count(),
+                graphics: family.supports_graphics(),
+                compute: family.supports_compute(),
+                transfer: family.explicitly_supports_transfers(),
+            })
+            .collect();
+        Self {
+            name: properties.device_name.clone(),
+            device_type: format!("{:?}", properties.device_type),
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            driver_version: properties.driver_version,
+            driver_name: properties.driver_name.clone(),
+            api_version: physical_device.api_version().to_string(),
+            device_uuid: properties.device_uuid,
+            pci_bus_info: PciBusInfo::new(properties),
+            instance_create_flags: instance_create_flags.as_raw(),
+            limits,
+            conservative_rasterization: ConservativeRasterProperties::new(properties),
+            extensions,
+            queue_families,
+        }
+    }
+}
+
+impl fmt::Display for DeviceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Device: {} ({})", self.name, self.device_type)?;
+        writeln!(
+            f,
+            "Vendor ID: {:#06x}, device ID: {:#06x}",
+            self.vendor_id, self.device_id,
+        )?;
+        writeln!(f, "Driver version: {:#x}", self.driver_version)?;
+        if let Some(driver_name) = &self.driver_name {
+            writeln!(f, "Driver name: {}", driver_name)?;
+        }
+        writeln!(f, "Vulkan API version: {}", self.api_version)?;
+        if let Some(device_uuid) = &self.device_uuid {
+            writeln!(f, "Device UUID: {}", self::format_uuid(device_uuid))?;
+        }
+        if let Some(pci) = &self.pci_bus_info {
+            writeln!(
+                f,
+                "PCI bus: {:04x}:{:02x}:{:02x}.{:x}",
+                pci.domain, pci.bus, pci.device, pci.function,
+            )?;
+        }
+        writeln!(
+            f,
+            "Instance create flags: {:#x}",
+            self.instance_create_flags,
+        )?;
+
+        let limits = &self.limits;
+        writeln!(f, "Limits:")?;
+        writeln!(
+            f,
+            "  max image dimension 2D: {}",
+            limits.max_image_dimension_2d,
+        )?;
+        writeln!(
+            f,
+            "  max push constants size: {}",
+            limits.max_push_constants_size,
+        )?;
+        writeln!(
+            f,
+            "  max bound descriptor sets: {}",
+            limits.max_bound_descriptor_sets,
+        )?;
+        writeln!(
+            f,
+            "  max per stage descriptor samplers: {}",
+            limits.max_per_stage_descriptor_samplers,
+        )?;
+        writeln!(
+            f,
+            "  max compute work group invocations: {}",
+            limits.max_compute_work_group_invocations,
+        )?;
+        writeln!(
+            f,
+            "  max sampler anisotropy: {}",
+            limits.max_sampler_anisotropy,
+        )?;
+
+        if let Some(conservative) = &self.conservative_rasterization {
+            writeln!(f, "Conservative rasterization:")?;
+            writeln!(
+                f,
+                "  primitive overestimation size: {}",
+                conservative.primitive_overestimation_size,
+            )?;
+            writeln!(
+                f,
+                "  max extra primitive overestimation size: {}",
+                conservative.max_extra_primitive_overestimation_size,
+            )?;
+            writeln!(
+                f,
+                "  primitive underestimation: {}",
+                conservative.primitive_underestimation,
+            )?;
+        }
+
+        writeln!(f, "Queue families:")?;
+        for family in &self.queue_families {
+            writeln!(
+                f,
+                "  #{}: {} queues (graphics: {}, compute: {}, transfer: {})",
+                family.id, family.queues_count, family.graphics, family.compute, family.transfer,
+            )?;
+        }
+
+        write!(f, "Extensions ({}):", self.extensions.len())?;
+        for extension in &self.extensions {
+            write!(f, "\n  {}", extension)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats UUID in its canonical form of hexadecimal groups (8-4-4-4-12 digits).
+pub fn format_uuid(uuid: &[u8; 16]) -> String {
+    let hex =
+        |bytes: &[u8]| -> String { bytes.iter().map(|byte| format!("{:02x}", byte)).collect() };
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex(&uuid[..4]),
+        hex(&uuid[4..6]),
+        hex(&uuid[6..8]),
+        hex(&uuid[8..10]),
+        hex(&uuid[10..]),
+    )
+}
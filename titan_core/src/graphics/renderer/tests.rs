@@ -0,0 +1,355 @@
+#![cfg(test)]
+
+use egui::{Pos2, Rect};
+use semver::Version;
+use vulkano::device::physical::Properties;
+use vulkano::device::Features;
+use vulkano::format::Format;
+use vulkano::instance::InstanceCreationError;
+use vulkano::swapchain::AcquireError;
+use vulkano::sync::FlushError;
+use vulkano::OomError;
+
+use super::{
+    error::{ApplicationInfoError, ErrorSeverity, QueueRequestError, RenderError, ResizeError},
+    present_region,
+    queue::*,
+    swapchain_dimensions, timestamp_period, ConservativeRasterProperties, HdrMetadata,
+    SparseProperties,
+};
+use crate::{config::Config, graphics::utils, window::PhysicalSize};
+
+/// Count of queues of each family of a typical discrete GPU.
+const QUEUES_COUNT: [usize; 3] = [16, 2, 8];
+
+fn queues_count(family: u32) -> Option<usize> {
+    QUEUES_COUNT.get(family as usize).copied()
+}
+
+#[test]
+fn test_queue_create_infos() {
+    let requests = [
+        QueueRequest::new(0, 1.0),
+        QueueRequest {
+            family: 2,
+            priorities: vec![0.5, 0.25],
+        },
+    ];
+    let create_infos = queue_create_infos(&requests, queues_count).unwrap();
+    assert_eq!(create_infos, [(0, 1.0), (2, 0.5), (2, 0.25)]);
+}
+
+#[test]
+fn test_invalid_queue_requests() {
+    let unknown = [QueueRequest::new(3, 1.0)];
+    assert!(matches!(
+        queue_create_infos(&unknown, queues_count),
+        Err(QueueRequestError::UnknownFamily(3)),
+    ));
+
+    let duplicate = [QueueRequest::new(0, 1.0), QueueRequest::new(0, 0.5)];
+    assert!(matches!(
+        queue_create_infos(&duplicate, queues_count),
+        Err(QueueRequestError::DuplicateFamily(0)),
+    ));
+
+    let too_many = [QueueRequest {
+        family: 1,
+        priorities: vec![1.0; 3],
+    }];
+    assert!(matches!(
+        queue_create_infos(&too_many, queues_count),
+        Err(QueueRequestError::InvalidQueueCount {
+            family: 1,
+            requested: 3,
+            available: 2,
+        }),
+    ));
+
+    let invalid_priority = [QueueRequest::new(0, 2.0)];
+    assert!(matches!(
+        queue_create_infos(&invalid_priority, queues_count),
+        Err(QueueRequestError::InvalidPriority(_)),
+    ));
+}
+
+#[test]
+fn test_application_info_nul() {
+    assert!(utils::check_application_info(&Config::default()).is_ok());
+
+    let config = Config::new("Hello\0World".to_string(), Version::new(0, 0, 0), false);
+    assert!(matches!(
+        utils::check_application_info(&config),
+        Err(ApplicationInfoError::ApplicationNameNul),
+    ));
+
+    let config = Config::default().with_engine_name("titan\0".to_string());
+    assert!(matches!(
+        utils::check_application_info(&config),
+        Err(ApplicationInfoError::EngineNameNul),
+    ));
+}
+
+#[test]
+fn test_render_error_severity() {
+    let out_of_date = RenderError::AcquireNextImage(AcquireError::OutOfDate);
+    assert_eq!(out_of_date.severity(), ErrorSeverity::Recoverable);
+
+    let surface_lost = RenderError::SubmitQueue(FlushError::SurfaceLost);
+    assert_eq!(surface_lost.severity(), ErrorSeverity::Recoverable);
+
+    let device_lost = RenderError::SubmitQueue(FlushError::DeviceLost);
+    assert_eq!(device_lost.severity(), ErrorSeverity::Recoverable);
+
+    let out_of_memory =
+        RenderError::AcquireNextImage(AcquireError::OomError(OomError::OutOfDeviceMemory));
+    assert_eq!(out_of_memory.severity(), ErrorSeverity::Fatal);
+
+    let resize = RenderError::Resize(ResizeError::SwapchainRecreation(
+        vulkano::swapchain::SwapchainCreationError::UnsupportedDimensions,
+    ));
+    assert_eq!(resize.severity(), ErrorSeverity::Fatal);
+}
+
+#[test]
+fn test_first_depth_format() {
+    let supported = [Format::D32_SFLOAT, Format::D24_UNORM_S8_UINT];
+    let supports = |format| supported.contains(&format);
+    assert_eq!(
+        utils::first_depth_format(false, supports),
+        Some(Format::D32_SFLOAT),
+    );
+    assert_eq!(
+        utils::first_depth_format(true, supports),
+        Some(Format::D24_UNORM_S8_UINT),
+    );
+    assert_eq!(utils::first_depth_format(true, |_| false), None);
+}
+
+#[test]
+fn test_instance_requirements_strip() {
+    let requirements = utils::OptionalInstanceRequirements {
+        validation_layer: true,
+        debug_utils: true,
+    };
+    let reduced = requirements
+        .strip(&InstanceCreationError::LayerNotPresent)
+        .unwrap();
+    assert_eq!(
+        reduced,
+        utils::OptionalInstanceRequirements {
+            validation_layer: false,
+            debug_utils: true,
+        },
+    );
+    assert_eq!(reduced.strip(&InstanceCreationError::LayerNotPresent), None);
+
+    let reduced = reduced
+        .strip(&InstanceCreationError::ExtensionNotPresent)
+        .unwrap();
+    assert!(!reduced.validation_layer && !reduced.debug_utils);
+    // Required extensions are not present, so this is a real failure.
+    assert_eq!(
+        reduced.strip(&InstanceCreationError::ExtensionNotPresent),
+        None,
+    );
+    assert_eq!(
+        requirements.strip(&InstanceCreationError::IncompatibleDriver),
+        None,
+    );
+}
+
+#[test]
+fn test_present_region() {
+    let extent = PhysicalSize::new(800, 600);
+    let damage = [
+        Rect::from_min_max(Pos2::new(10.2, 20.0), Pos2::new(50.0, 40.7)),
+        // Partially outside of the image.
+        Rect::from_min_max(Pos2::new(-5.0, 250.0), Pos2::new(20.0, 400.0)),
+        // Fully outside of the image.
+        Rect::from_min_max(Pos2::new(500.0, 10.0), Pos2::new(600.0, 20.0)),
+    ];
+    let region = present_region(&damage, 2.0, extent);
+    assert_eq!(region.rectangles.len(), 2);
+    assert_eq!(region.rectangles[0].offset, [20, 40]);
+    assert_eq!(region.rectangles[0].extent, [80, 42]);
+    assert_eq!(region.rectangles[1].offset, [0, 500]);
+    assert_eq!(region.rectangles[1].extent, [40, 100]);
+}
+
+#[test]
+fn test_swapchain_dimensions() {
+    use vulkano::swapchain::SurfaceTransform;
+
+    // Current extent of the surface is used as is.
+    let extent = swapchain_dimensions(Some([1080, 1920]), [1920, 1080], SurfaceTransform::Rotate90);
+    assert_eq!(extent, [1080, 1920]);
+
+    // Otherwise the window size is swapped on displays rotated by 90 or 270 degrees.
+    let extent = swapchain_dimensions(None, [1920, 1080], SurfaceTransform::Rotate270);
+    assert_eq!(extent, [1080, 1920]);
+    let extent = swapchain_dimensions(None, [1920, 1080], SurfaceTransform::Rotate180);
+    assert_eq!(extent, [1920, 1080]);
+}
+
+#[test]
+fn test_sparse_properties() {
+    let properties = Properties {
+        residency_standard2_d_block_shape: true,
+        residency_non_resident_strict: true,
+        ..Properties::default()
+    };
+    let features = Features {
+        sparse_binding: true,
+        ..Features::none()
+    };
+    let sparse = SparseProperties::new(&properties, &features);
+    assert_eq!(
+        sparse,
+        SparseProperties {
+            sparse_binding: true,
+            residency_standard_2d_block_shape: true,
+            residency_non_resident_strict: true,
+            ..SparseProperties::default()
+        },
+    );
+
+    let unsupported = SparseProperties::new(&Properties::default(), &Features::none());
+    assert!(!unsupported.sparse_binding);
+}
+
+#[test]
+fn test_conservative_raster_properties() {
+    assert_eq!(
+        ConservativeRasterProperties::new(&Properties::default()),
+        None
+    );
+
+    let properties = Properties {
+        primitive_overestimation_size: Some(0.5),
+        max_extra_primitive_overestimation_size: Some(0.75),
+        extra_primitive_overestimation_size_granularity: Some(0.25),
+        primitive_underestimation: Some(true),
+        conservative_point_and_line_rasterization: Some(true),
+        degenerate_triangles_rasterized: Some(false),
+        degenerate_lines_rasterized: Some(false),
+        fully_covered_fragment_shader_input_variable: Some(true),
+        conservative_rasterization_post_depth_coverage: Some(false),
+        ..Properties::default()
+    };
+    let conservative = ConservativeRasterProperties::new(&properties).unwrap();
+    assert_eq!(conservative.max_extra_primitive_overestimation_size, 0.75);
+    assert!(conservative.primitive_underestimation);
+    assert!(!conservative.degenerate_triangles_rasterized);
+}
+
+#[test]
+fn test_timestamp_period() {
+    let properties = Properties {
+        timestamp_compute_and_graphics: true,
+        timestamp_period: 52.08,
+        ..Properties::default()
+    };
+    assert_eq!(timestamp_period(&properties), Some(52.08));
+
+    let properties = Properties {
+        timestamp_compute_and_graphics: false,
+        ..properties
+    };
+    assert_eq!(timestamp_period(&properties), None);
+}
+
+#[test]
+fn test_hdr_metadata() {
+    let metadata = HdrMetadata {
+        max_luminance: 600.0,
+        ..HdrMetadata::default()
+    };
+    let raw = ash::vk::HdrMetadataEXT::from(metadata);
+    assert_eq!(raw.s_type, ash::vk::StructureType::HDR_METADATA_EXT);
+    assert_eq!(
+        (raw.display_primary_red.x, raw.display_primary_red.y),
+        (0.708, 0.292)
+    );
+    assert_eq!((raw.white_point.x, raw.white_point.y), (0.3127, 0.3290));
+    assert_eq!(raw.max_luminance, 600.0);
+    assert_eq!(raw.max_frame_average_light_level, 400.0);
+}
+
+#[test]
+fn test_report_instance_create_flags() {
+    use super::report::{DeviceLimitsReport, DeviceReport};
+
+    assert!(utils::INSTANCE_CREATE_FLAGS.is_empty());
+    let report = DeviceReport {
+        name: "Test device".into(),
+        device_type: "Cpu".into(),
+        vendor_id: 0,
+        device_id: 0,
+        driver_version: 0,
+        driver_name: None,
+        api_version: "1.2.0".into(),
+        device_uuid: None,
+        pci_bus_info: None,
+        instance_create_flags: utils::INSTANCE_CREATE_FLAGS.as_raw(),
+        limits: DeviceLimitsReport {
+            max_image_dimension_2d: 4096,
+            max_push_constants_size: 128,
+            max_bound_descriptor_sets: 4,
+            max_per_stage_descriptor_samplers: 16,
+            max_compute_work_group_invocations: 128,
+            max_sampler_anisotropy: 1.0,
+        },
+        conservative_rasterization: None,
+        extensions: Vec::new(),
+        queue_families: Vec::new(),
+    };
+    let description = report.to_string();
+    assert!(description.contains("Instance create flags: 0x0\n"));
+}
+
+#[test]
+fn test_find_unified_family() {
+    // Family #1 supports graphics and present.
+    let families = [(0, false), (1, true), (2, true)];
+    let unified = utils::find_unified_family(families, |family| Ok::<_, ()>(family != 2));
+    assert_eq!(unified, Ok(Some(1)));
+
+    // Graphics family #0 cannot present, only compute family #1 can.
+    let families = [(0, true), (1, false)];
+    let unified = utils::find_unified_family(families, |family| Ok::<_, ()>(family == 1));
+    assert_eq!(unified, Ok(None));
+
+    // Present support of non-graphics families is not queried.
+    let families = [(0, false), (1, true)];
+    let unified = utils::find_unified_family(families, |family| match family {
+        0 => Err(()),
+        _ => Ok(true),
+    });
+    assert_eq!(unified, Ok(Some(1)));
+}
+
+#[test]
+fn test_device_identity() {
+    use super::{report::format_uuid, PciBusInfo};
+
+    assert_eq!(PciBusInfo::new(&Properties::default()), None);
+    let properties = Properties {
+        pci_domain: Some(0),
+        pci_bus: Some(1),
+        pci_device: Some(0),
+        pci_function: Some(0),
+        ..Properties::default()
+    };
+    let pci = PciBusInfo::new(&properties).unwrap();
+    assert_eq!(
+        (pci.domain, pci.bus, pci.device, pci.function),
+        (0, 1, 0, 0)
+    );
+
+    let uuid = [
+        0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd,
+        0xef,
+    ];
+    assert_eq!(format_uuid(&uuid), "12345678-9abc-def0-0123-456789abcdef");
+}
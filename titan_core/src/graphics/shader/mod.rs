@@ -26,6 +26,16 @@ pub mod vertex {
         vulkano_shaders::shader! {
             ty: "vertex",
             path: "src/graphics/shader/ui.vert",
+            include: ["src/graphics/shader"],
+        }
+    }
+
+    /// UI vertex shader utilities for instanced rendering.
+    pub mod instanced_vertex {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            path: "src/graphics/shader/ui_instanced.vert",
+            include: ["src/graphics/shader"],
         }
     }
 
@@ -34,6 +44,67 @@ pub mod fragment {
         vulkano_shaders::shader! {
             ty: "fragment",
             path: "src/graphics/shader/ui.frag",
+            include: ["src/graphics/shader"],
+        }
+    }
+
+    /// UI fragment shader utilities for bindless textures.
+    pub mod bindless_fragment {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "src/graphics/shader/ui_bindless.frag",
+            include: ["src/graphics/shader"],
+        }
+    }
+
+    /// UI fragment shader utilities for multi-channel signed distance field textures.
+    pub mod msdf_fragment {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "src/graphics/shader/ui_msdf.frag",
+            include: ["src/graphics/shader"],
+        }
+    }
+
+    /// UI fragment shader utilities which also write element ids of meshes.
+    pub mod element_id_fragment {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "src/graphics/shader/ui.frag",
+            define: [("ELEMENT_ID", "1")],
+            include: ["src/graphics/shader"],
+        }
+    }
+
+    /// UI fragment shader utilities for bindless textures which also write element ids of meshes.
+    pub mod element_id_bindless_fragment {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "src/graphics/shader/ui_bindless.frag",
+            define: [("ELEMENT_ID", "1")],
+            include: ["src/graphics/shader"],
+        }
+    }
+
+    /// UI fragment shader utilities for multi-channel signed distance field textures
+    /// which also write element ids of meshes.
+    pub mod element_id_msdf_fragment {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            path: "src/graphics/shader/ui_msdf.frag",
+            define: [("ELEMENT_ID", "1")],
+            include: ["src/graphics/shader"],
+        }
+    }
+}
+
+/// Shaders which are used in post effects.
+pub mod effect {
+    /// Separable gaussian blur compute shader utilities.
+    pub mod blur {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            path: "src/graphics/shader/blur.comp",
         }
     }
 }
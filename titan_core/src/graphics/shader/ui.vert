@@ -7,16 +7,21 @@ layout(location = 2) in vec4 color;
 layout(location = 0) out vec4 outColor;
 layout(location = 1) out vec2 outUV;
 
-layout(push_constant) uniform PushConstants {
-    vec2 screen_size;
-} pushConstants;
+#include <ui_push_constants.glsl>
 
 out gl_PerVertex {
     vec4 gl_Position;
 };
 
 void main() {
-    gl_Position = vec4(2.0 * position / pushConstants.screen_size - 1.0, 0.0, 1.0);
+    vec2 ndc = 2.0 * position / pushConstants.screen_size - 1.0;
+    ndc.y *= pushConstants.y_axis;
+    // Rotation matrix is stored in row-major order.
+    ndc = vec2(
+        pushConstants.rotation[0] * ndc.x + pushConstants.rotation[1] * ndc.y,
+        pushConstants.rotation[2] * ndc.x + pushConstants.rotation[3] * ndc.y
+    );
+    gl_Position = vec4(ndc, 0.0, 1.0);
     outColor = color;
     outUV = uv;
 }
@@ -0,0 +1,36 @@
+#version 450
+
+layout(location = 0) in vec4 color;
+layout(location = 1) in vec2 uv;
+
+layout(location = 0) out vec4 outColor;
+#ifdef ELEMENT_ID
+// Element id of the mesh (e.g. for GPU-side picking),
+// written only into subpasses with an element id attachment.
+layout(location = 1) out uint outElementId;
+#endif
+
+#include <ui_push_constants.glsl>
+
+// Color filter matrix is stored in row-major order.
+vec3 filterColor(vec3 color) {
+    float m[9] = pushConstants.color_filter;
+    return mat3(m[0], m[3], m[6], m[1], m[4], m[7], m[2], m[5], m[8]) * color;
+}
+
+// Length must be equal to `TEXTURE_ARRAY_LEN` constant.
+layout(binding = 0, set = 0) uniform sampler2D textures[128];
+
+void main() {
+    outColor = color * texture(textures[pushConstants.texture_index], uv);
+    // Filter matrices have negative entries, but gamma is undefined for negative colors.
+    vec3 rgb = max(filterColor(outColor.rgb), 0.0);
+    // Gamma is applied to straight color, so that it does not depend on alpha.
+    rgb = outColor.a > 0.0 ? rgb / outColor.a : vec3(0.0);
+    rgb = pow(rgb, vec3(pushConstants.gamma)) * pushConstants.brightness;
+    // Paper white is applied last, so that gamma does not change brightness of HDR white.
+    outColor.rgb = rgb * outColor.a * pushConstants.color_scale;
+#ifdef ELEMENT_ID
+    outElementId = pushConstants.element_id;
+#endif
+}
@@ -0,0 +1,34 @@
+#version 450
+
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+
+// Per-instance attributes.
+layout(location = 3) in vec2 offset;
+layout(location = 4) in vec2 scale;
+layout(location = 5) in vec2 uv_offset;
+layout(location = 6) in vec2 uv_scale;
+layout(location = 7) in vec4 instance_color;
+
+layout(location = 0) out vec4 outColor;
+layout(location = 1) out vec2 outUV;
+
+#include <ui_push_constants.glsl>
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+void main() {
+    vec2 ndc = 2.0 * (position * scale + offset) / pushConstants.screen_size - 1.0;
+    ndc.y *= pushConstants.y_axis;
+    // Rotation matrix is stored in row-major order.
+    ndc = vec2(
+        pushConstants.rotation[0] * ndc.x + pushConstants.rotation[1] * ndc.y,
+        pushConstants.rotation[2] * ndc.x + pushConstants.rotation[3] * ndc.y
+    );
+    gl_Position = vec4(ndc, 0.0, 1.0);
+    outColor = color * instance_color;
+    outUV = uv * uv_scale + uv_offset;
+}
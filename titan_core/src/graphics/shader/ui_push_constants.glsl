@@ -0,0 +1,15 @@
+// Push constants shared by all UI shaders.
+// Layout must match push constants created by UI draw system.
+layout(push_constant) uniform PushConstants {
+    vec2 screen_size;
+    float y_axis;
+    uint texture_index;
+    float color_scale;
+    float msdf_threshold;
+    float msdf_smoothing;
+    float gamma;
+    float brightness;
+    float rotation[4];
+    float color_filter[9];
+    uint element_id;
+} pushConstants;
@@ -0,0 +1,103 @@
+//! Surface utilities which are independent of windowing library.
+//!
+//! Surfaces of `winit` windows are created by the renderer itself;
+//! these utilities are useful for windows of other libraries (e.g. SDL2 or GLFW)
+//! which expose raw window handle.
+
+use std::sync::Arc;
+
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+use thiserror::Error;
+use vulkano::device::physical::PhysicalDevice;
+use vulkano::instance::Instance;
+use vulkano::swapchain::{CapabilitiesError, Surface, SurfaceCreationError};
+
+/// Error that can happen when creating surface from raw window handle.
+#[derive(Debug, Error)]
+pub enum RawSurfaceCreationError {
+    #[error("window handle is not supported on this platform")]
+    UnsupportedHandle,
+
+    #[error("surface creation failure: {0}")]
+    SurfaceCreation(#[from] SurfaceCreationError),
+}
+
+/// Creates surface for the window with raw window handle.
+///
+/// Instance must be created with surface extensions of the platform enabled.
+/// Window is owned by the surface, so it outlives the surface.
+///
+/// Surfaces of macOS and iOS windows are not supported,
+/// because their views must be backed by Metal layer.
+///
+pub fn create_surface<W>(
+    instance: Arc<Instance>,
+    window: W,
+) -> Result<Arc<Surface<W>>, RawSurfaceCreationError>
+where
+    W: HasRawWindowHandle,
+{
+    let surface = match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        RawWindowHandle::Windows(handle) => unsafe {
+            Surface::from_hwnd(instance, handle.hinstance, handle.hwnd, window)?
+        },
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd",
+        ))]
+        RawWindowHandle::Xlib(handle) => unsafe {
+            Surface::from_xlib(instance, handle.display, handle.window, window)?
+        },
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd",
+        ))]
+        RawWindowHandle::Xcb(handle) => unsafe {
+            Surface::from_xcb(instance, handle.connection, handle.window, window)?
+        },
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd",
+        ))]
+        RawWindowHandle::Wayland(handle) => unsafe {
+            Surface::from_wayland(instance, handle.display, handle.surface, window)?
+        },
+        #[cfg(target_os = "android")]
+        RawWindowHandle::Android(handle) => unsafe {
+            Surface::from_anativewindow(instance, handle.a_native_window, window)?
+        },
+        _ => return Err(RawSurfaceCreationError::UnsupportedHandle),
+    };
+    Ok(surface)
+}
+
+/// Retrieves index of queue family of physical device which supports both graphics
+/// and presentation to the surface, or `None` if they must be separate.
+///
+/// One queue of such family could both render and present, so swapchain images
+/// could be used in exclusive sharing mode without ownership transfers.
+///
+pub fn unified_queue_family<W>(
+    surface: &Surface<W>,
+    physical_device: PhysicalDevice,
+) -> Result<Option<u32>, CapabilitiesError> {
+    super::utils::find_unified_family(
+        physical_device
+            .queue_families()
+            .map(|family| (family.id(), family.supports_graphics())),
+        |family| {
+            let family = physical_device.queue_family_by_id(family).unwrap();
+            surface.is_supported(family)
+        },
+    )
+}
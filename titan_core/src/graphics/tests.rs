@@ -0,0 +1,18 @@
+#![cfg(test)]
+
+use vulkano::instance::InstanceExtensions;
+
+use super::instance;
+
+#[test]
+fn test_available_extensions() {
+    // Vulkan library could be missing on the machine which runs tests.
+    let extensions = match instance::available_extensions() {
+        Ok(extensions) => extensions,
+        Err(_) => return,
+    };
+    assert_ne!(extensions, InstanceExtensions::none());
+
+    // Library is cached, so layers are enumerated without loading it again.
+    assert!(instance::available_layers().is_ok());
+}
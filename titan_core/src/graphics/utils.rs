@@ -1,16 +1,19 @@
 //! General graphics utilities for game engine.
 
+use std::convert::Infallible;
 use std::sync::Arc;
 
 use vulkano::device::physical::{PhysicalDevice, PhysicalDeviceType, QueueFamily};
 use vulkano::device::{DeviceExtensions, Features};
 use vulkano::format::Format;
-use vulkano::instance::{ApplicationInfo, Instance, InstanceCreationError};
+use vulkano::instance::{ApplicationInfo, Instance, InstanceCreationError, InstanceExtensions};
 use vulkano::swapchain::{Capabilities, ColorSpace, Surface};
 use vulkano_win::required_extensions;
 use winit::window::Window;
 
-use crate::config::{Config, ENGINE_NAME, ENGINE_VERSION};
+use crate::config::{Config, DeviceFeatureChain, DeviceSelector, ValidationFeatures};
+
+use super::renderer::error::ApplicationInfoError;
 
 /// Convert [`semver::Version`] Version struct into [`vulkano::Version`] struct.
 #[inline(always)]
@@ -22,30 +25,263 @@ const fn to_vk_version(version: &semver::Version) -> vulkano::Version {
     }
 }
 
+/// Environment variable which is read by Khronos validation layer
+/// to enable additional validation features.
+const LAYER_ENABLES_VAR: &str = "VK_LAYER_ENABLES";
+
+/// Checks if optional validation features of Khronos validation layer are enabled.
+///
+/// Instance creation of vulkano does not allow to chain `VkValidationFeaturesEXT`,
+/// and environment of the process must not be changed while other threads could read it,
+/// so features are enabled only if user sets [`LAYER_ENABLES_VAR`] environment variable.
+/// Otherwise the value of this variable for requested features is logged.
+///
+fn check_validation_features(validation_features: &ValidationFeatures) {
+    if std::env::var_os(LAYER_ENABLES_VAR).is_some() {
+        log::info!(
+            "validation features are set by {} variable",
+            LAYER_ENABLES_VAR,
+        );
+        return;
+    }
+    let features = [
+        (
+            validation_features.gpu_assisted,
+            "VK_VALIDATION_FEATURE_ENABLE_GPU_ASSISTED_EXT",
+        ),
+        (
+            validation_features.best_practices,
+            "VK_VALIDATION_FEATURE_ENABLE_BEST_PRACTICES_EXT",
+        ),
+        (
+            validation_features.synchronization,
+            "VK_VALIDATION_FEATURE_ENABLE_SYNCHRONIZATION_VALIDATION_EXT",
+        ),
+    ];
+    let enabled: Vec<_> = features
+        .iter()
+        .filter_map(|&(enabled, name)| enabled.then(|| name))
+        .collect();
+    if enabled.is_empty() {
+        return;
+    }
+    let separator = if cfg!(windows) { ";" } else { ":" };
+    log::warn!(
+        "validation features cannot be enabled by the engine, set {}={} to enable them",
+        LAYER_ENABLES_VAR,
+        enabled.join(separator),
+    );
+}
+
+/// Checks if application info of the config can be passed to Vulkan.
+///
+/// Names are converted into C strings on instance creation,
+/// so names with interior NUL bytes are rejected instead of panicking.
+///
+pub fn check_application_info(config: &Config) -> Result<(), ApplicationInfoError> {
+    if config.name().contains('\0') {
+        return Err(ApplicationInfoError::ApplicationNameNul);
+    }
+    if config.engine_name().contains('\0') {
+        return Err(ApplicationInfoError::EngineNameNul);
+    }
+    Ok(())
+}
+
+/// Name of Khronos validation layer.
+const VALIDATION_LAYER: &str = "VK_LAYER_KHRONOS_validation";
+
+/// Optional requirements of the instance which can be stripped if its creation fails.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OptionalInstanceRequirements {
+    /// Khronos validation layer.
+    pub validation_layer: bool,
+
+    /// `VK_EXT_debug_utils` extension.
+    pub debug_utils: bool,
+}
+
+impl OptionalInstanceRequirements {
+    /// Strips requirement which could cause given error of instance creation.
+    ///
+    /// Returns `None` if there is nothing to strip, so the error is a real failure.
+    ///
+    pub fn strip(self, error: &InstanceCreationError) -> Option<Self> {
+        match error {
+            InstanceCreationError::LayerNotPresent if self.validation_layer => {
+                log::warn!(
+                    "{} layer is not present, retrying instance creation without it",
+                    VALIDATION_LAYER,
+                );
+                Some(Self {
+                    validation_layer: false,
+                    ..self
+                })
+            }
+            InstanceCreationError::ExtensionNotPresent if self.debug_utils => {
+                log::warn!(
+                    "VK_EXT_debug_utils is not present, retrying instance creation without it"
+                );
+                Some(Self {
+                    debug_utils: false,
+                    ..self
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Flags used on instance creation.
+///
+/// Instance creation of vulkano does not allow to pass any flags
+/// (e.g. to enable portability enumeration), so instance is always created without them.
+///
+pub const INSTANCE_CREATE_FLAGS: ash::vk::InstanceCreateFlags =
+    ash::vk::InstanceCreateFlags::empty();
+
 /// Create instance of Vulkan (with low-level vkInstance handle).
 ///
-/// Will enable `VK_EXT_debug_utils` extension if
-/// validation is enabled by config.
+/// Will enable `VK_EXT_debug_utils` extension if validation or debug messenger
+/// is enabled by config, and optional validation features if validation is enabled.
+///
+/// `VK_KHR_get_physical_device_properties2` extension is enabled if requested API version
+/// is Vulkan 1.0 (it is core since Vulkan 1.1) and it is available.
+///
+/// If [instance retry](Config::instance_retry) is enabled, validation layer and
+/// `VK_EXT_debug_utils` extension are stripped one by one if they are not present.
 ///
 pub fn create_instance(config: &Config) -> Result<Arc<Instance>, InstanceCreationError> {
     let info = ApplicationInfo {
         application_name: Some(config.name().into()),
         application_version: Some(self::to_vk_version(config.version())),
-        engine_name: Some(ENGINE_NAME.into()),
-        engine_version: Some(self::to_vk_version(&*ENGINE_VERSION)),
+        engine_name: Some(config.engine_name().into()),
+        engine_version: Some(self::to_vk_version(config.engine_version())),
     };
-    let extensions = {
-        let mut extensions = required_extensions();
-        if config.enable_validation() {
-            extensions.ext_debug_utils = true;
+    let api_version = config.requested_api_version();
+    let mut requirements = OptionalInstanceRequirements {
+        validation_layer: config.enable_validation(),
+        debug_utils: config.enable_validation() || config.debug_messenger().is_enabled(),
+    };
+    let mut extensions = required_extensions();
+    if api_version < vulkano::Version::V1_1 {
+        let available = super::instance::available_extensions()
+            .map(|available| available.khr_get_physical_device_properties2)
+            .unwrap_or(false);
+        extensions.khr_get_physical_device_properties2 = available;
+    }
+    if let Ok(available_layers) = super::instance::available_layers() {
+        let names: Vec<_> = available_layers.iter().map(|layer| layer.name()).collect();
+        log::debug!("available instance layers: {:?}", names);
+    }
+    if config.enable_validation() {
+        self::check_validation_features(config.validation_features());
+    }
+
+    let instance = loop {
+        let extensions = InstanceExtensions {
+            ext_debug_utils: requirements.debug_utils,
+            ..extensions
+        };
+        let layers = requirements.validation_layer.then(|| VALIDATION_LAYER);
+        match Instance::new(Some(&info), api_version, &extensions, layers) {
+            Ok(instance) => break instance,
+            Err(error) if config.instance_retry() => match requirements.strip(&error) {
+                Some(reduced) => requirements = reduced,
+                None => return Err(error),
+            },
+            Err(error) => return Err(error),
         }
-        extensions
     };
-    let layers = config
-        .enable_validation()
-        .then(|| "VK_LAYER_KHRONOS_validation");
+    log::info!(
+        "requested Vulkan {}, instance uses Vulkan {}",
+        api_version,
+        instance.api_version(),
+    );
+    Ok(instance)
+}
+
+/// Convert [`DeviceFeatureChain`] into [`Features`] struct.
+pub fn to_vk_features(device_features: &DeviceFeatureChain) -> Features {
+    let descriptor_indexing = device_features.descriptor_indexing();
+    Features {
+        timeline_semaphore: device_features.timeline_semaphore(),
+        runtime_descriptor_array: descriptor_indexing,
+        shader_sampled_image_array_dynamic_indexing: descriptor_indexing,
+        shader_sampled_image_array_non_uniform_indexing: descriptor_indexing,
+        descriptor_binding_partially_bound: descriptor_indexing,
+        descriptor_binding_variable_descriptor_count: descriptor_indexing,
+        ..Features::none()
+    }
+}
+
+/// Retrieves names of features from [`DeviceFeatureChain`]
+/// which are not supported by physical device.
+pub fn unsupported_features(
+    physical_device: PhysicalDevice,
+    device_features: &DeviceFeatureChain,
+) -> Vec<&'static str> {
+    let supported = physical_device.supported_features();
+    let requested = self::to_vk_features(device_features);
+    let features = [
+        (
+            requested.timeline_semaphore,
+            supported.timeline_semaphore,
+            "timeline_semaphore",
+        ),
+        (
+            requested.runtime_descriptor_array,
+            supported.runtime_descriptor_array,
+            "runtime_descriptor_array",
+        ),
+        (
+            requested.shader_sampled_image_array_dynamic_indexing,
+            supported.shader_sampled_image_array_dynamic_indexing,
+            "shader_sampled_image_array_dynamic_indexing",
+        ),
+        (
+            requested.shader_sampled_image_array_non_uniform_indexing,
+            supported.shader_sampled_image_array_non_uniform_indexing,
+            "shader_sampled_image_array_non_uniform_indexing",
+        ),
+        (
+            requested.descriptor_binding_partially_bound,
+            supported.descriptor_binding_partially_bound,
+            "descriptor_binding_partially_bound",
+        ),
+        (
+            requested.descriptor_binding_variable_descriptor_count,
+            supported.descriptor_binding_variable_descriptor_count,
+            "descriptor_binding_variable_descriptor_count",
+        ),
+    ];
+    let mut unsupported: Vec<_> = features
+        .iter()
+        .filter(|&&(requested, supported, _)| requested && !supported)
+        .map(|&(_, _, name)| name)
+        .collect();
+    let core_1_2 = physical_device.api_version() >= vulkano::Version::V1_2;
+    if device_features.descriptor_indexing() && !core_1_2 {
+        unsupported.push("descriptor_indexing (requires Vulkan 1.2)");
+    }
+    unsupported
+}
 
-    Instance::new(Some(&info), vulkano::Version::V1_2, &extensions, layers)
+/// Retrieves device extensions which are needed to enable features
+/// from [`DeviceFeatureChain`] on physical device.
+///
+/// Features which were promoted to core of physical device API version
+/// do not need any extensions.
+///
+pub fn feature_extensions(
+    physical_device: PhysicalDevice,
+    device_features: &DeviceFeatureChain,
+) -> DeviceExtensions {
+    let core_1_2 = physical_device.api_version() >= vulkano::Version::V1_2;
+    DeviceExtensions {
+        khr_timeline_semaphore: device_features.timeline_semaphore() && !core_1_2,
+        ..DeviceExtensions::none()
+    }
 }
 
 /// Internal struct for representing suitable physical device with its queue families.
@@ -56,60 +292,139 @@ pub struct SuitablePhysicalDevice<'a> {
     pub transfer_family: Option<QueueFamily<'a>>,
 }
 
+/// Queries present support of the surface for all queue families of physical device at once.
+///
+/// Element of returned vector is indexed by queue family index.
+/// Queue families which support could not be queried are considered unsupported.
+///
+pub fn present_support(physical_device: PhysicalDevice, surface: &Surface<Window>) -> Vec<bool> {
+    physical_device
+        .queue_families()
+        .map(|queue| match surface.is_supported(queue) {
+            Ok(supported) => supported,
+            Err(error) => {
+                log::warn!(
+                    "failed to query present support of queue family {}: {}",
+                    queue.id(),
+                    error,
+                );
+                false
+            }
+        })
+        .collect()
+}
+
+/// Finds the first graphics queue family which also supports presentation.
+///
+/// Queue families are given by their indices with graphics support.
+/// Present support is queried only for graphics queue families.
+///
+pub fn find_unified_family<E>(
+    families: impl IntoIterator<Item = (u32, bool)>,
+    mut supports_present: impl FnMut(u32) -> Result<bool, E>,
+) -> Result<Option<u32>, E> {
+    for (family, supports_graphics) in families {
+        if supports_graphics && supports_present(family)? {
+            return Ok(Some(family));
+        }
+    }
+    Ok(None)
+}
+
 /// Filter suitable physical device from all of them.
 ///
 /// Will check for provided extensions and features support.
+/// If preferred physical device is provided and it is suitable, it will be selected;
+/// otherwise physical device with the best score will be selected.
+///
+/// Queue family which supports both graphics and presentation is preferred
+/// for both of them, so one queue could render and present.
 ///
 pub fn suitable_physical_device<'a>(
     physical_devices: impl ExactSizeIterator<Item = PhysicalDevice<'a>>,
     surface: &Arc<Surface<Window>>,
     required_extensions: &DeviceExtensions,
     required_features: &Features,
+    preferred_device: Option<&DeviceSelector>,
 ) -> Option<SuitablePhysicalDevice<'a>> {
-    physical_devices
-        .filter(|physical_device| {
+    let suitable: Vec<_> = physical_devices
+        .enumerate()
+        .filter(|(_, physical_device)| {
             let extensions = physical_device.supported_extensions();
             let features = physical_device.supported_features();
             extensions.is_superset_of(required_extensions)
                 && features.is_superset_of(required_features)
         })
-        .filter_map(|physical_device| {
-            let graphics_family = physical_device
-                .queue_families()
-                .find(QueueFamily::supports_graphics);
-            let present_family = physical_device
-                .queue_families()
-                .find(|&queue| surface.is_supported(queue).unwrap_or(false));
+        .filter_map(|(index, physical_device)| {
+            let present_support = self::present_support(physical_device, surface);
+            let unified_family = self::find_unified_family(
+                physical_device
+                    .queue_families()
+                    .map(|family| (family.id(), family.supports_graphics())),
+                |family| Ok::<_, Infallible>(present_support[family as usize]),
+            )
+            .unwrap_or_else(|never| match never {})
+            .and_then(|family| physical_device.queue_family_by_id(family));
+            let graphics_family = unified_family.or_else(|| {
+                physical_device
+                    .queue_families()
+                    .find(QueueFamily::supports_graphics)
+            });
+            let present_family = unified_family.or_else(|| {
+                physical_device
+                    .queue_families()
+                    .find(|queue| present_support[queue.id() as usize])
+            });
             let transfer_family = physical_device
                 .queue_families()
                 .find(QueueFamily::explicitly_supports_transfers);
-            match (graphics_family, present_family, transfer_family) {
+            let suitable = match (graphics_family, present_family, transfer_family) {
                 (Some(graphics_family), Some(present_family), Some(transfer_family)) => {
-                    Some(SuitablePhysicalDevice {
+                    SuitablePhysicalDevice {
                         physical_device,
                         graphics_family,
                         present_family: Some(present_family),
                         transfer_family: Some(transfer_family),
-                    })
-                }
-                (Some(graphics_family), Some(present_family), None) => {
-                    Some(SuitablePhysicalDevice {
-                        physical_device,
-                        graphics_family,
-                        present_family: Some(present_family),
-                        transfer_family: None,
-                    })
+                    }
                 }
-                (Some(graphics_family), None, None) => Some(SuitablePhysicalDevice {
+                (Some(graphics_family), Some(present_family), None) => SuitablePhysicalDevice {
+                    physical_device,
+                    graphics_family,
+                    present_family: Some(present_family),
+                    transfer_family: None,
+                },
+                (Some(graphics_family), None, None) => SuitablePhysicalDevice {
                     physical_device,
                     graphics_family,
                     present_family: None,
                     transfer_family: None,
-                }),
-                _ => None,
-            }
+                },
+                _ => return None,
+            };
+            Some((index, suitable))
         })
-        .max_by_key(|suitable| self::score(&suitable.physical_device))
+        .collect();
+
+    let preferred = preferred_device.and_then(|selector| {
+        let position = suitable.iter().position(|(index, suitable)| {
+            let properties = suitable.physical_device.properties();
+            selector.matches(*index, &properties.device_name, properties.device_type)
+        });
+        if position.is_none() {
+            log::warn!(
+                "preferred physical device {:?} is not suitable or not found",
+                selector,
+            );
+        }
+        position
+    });
+    let selected = match preferred {
+        Some(position) => suitable.into_iter().nth(position),
+        None => suitable
+            .into_iter()
+            .max_by_key(|(_, suitable)| self::score(&suitable.physical_device)),
+    };
+    selected.map(|(_, suitable)| suitable)
 }
 
 /// Calculates internal score of given physical device.
@@ -133,19 +448,48 @@ fn score(physical_device: &PhysicalDevice) -> u32 {
     Format::D24_UNORM_S8_UINT,
 ];
 
+/// Depth formats with stencil component which are suitable for rendering backend.
+pub const SUITABLE_STENCIL_FORMATS: [Format; 3] = [
+    Format::D32_SFLOAT_S8_UINT,
+    Format::D24_UNORM_S8_UINT,
+    Format::D16_UNORM_S8_UINT,
+];
+
 /// Retrieves suitable depth stencil format (see [`SUITABLE_DEPTH_STENCIL_FORMATS`]),
 /// if supported by physical device.
 ///
 /// If none of suitable depth stencil formats are supported,
 /// returns [`Format::D16Unorm`] which is guaranteed to be supported.
 pub fn suitable_depth_stencil_format(physical_device: PhysicalDevice) -> Format {
-    *SUITABLE_DEPTH_STENCIL_FORMATS
+    self::find_depth_format(physical_device, false).unwrap_or(Format::D16_UNORM)
+}
+
+/// Finds the first depth format which supports depth attachment with optimal tiling.
+///
+/// If stencil is wanted, only formats with stencil component are considered
+/// (see [`SUITABLE_STENCIL_FORMATS`]), otherwise [`SUITABLE_DEPTH_STENCIL_FORMATS`] are used.
+///
+pub fn find_depth_format(physical_device: PhysicalDevice, want_stencil: bool) -> Option<Format> {
+    self::first_depth_format(want_stencil, |format| {
+        let properties = format.properties(physical_device);
+        properties.optimal_tiling_features.depth_stencil_attachment
+    })
+}
+
+/// Finds the first candidate depth format which is supported as depth attachment.
+pub fn first_depth_format(
+    want_stencil: bool,
+    supports_attachment: impl Fn(Format) -> bool,
+) -> Option<Format> {
+    let candidates: &[Format] = if want_stencil {
+        &SUITABLE_STENCIL_FORMATS
+    } else {
+        &SUITABLE_DEPTH_STENCIL_FORMATS
+    };
+    candidates
         .iter()
-        .find(|format| {
-            let properties = format.properties(physical_device);
-            properties.optimal_tiling_features.depth_stencil_attachment
-        })
-        .unwrap_or(&Format::D16_UNORM)
+        .copied()
+        .find(|&format| supports_attachment(format))
 }
 
 /// Image format which is suitable for rendering backend.
@@ -158,3 +158,48 @@ fn from(vertex: epaint::Vertex) -> Self {
         Self::new(position, uv, color)
     }
 }
+
+/// Per-instance attributes of the base UI mesh drawn instanced.
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+pub struct UiInstance {
+    /// Translation of base mesh positions on the screen.
+    pub offset: Position2,
+    /// Scale of base mesh positions, applied before translation.
+    pub scale: Position2,
+    /// Translation of base mesh UV positions on the texture.
+    pub uv_offset: Position2,
+    /// Scale of base mesh UV positions, applied before translation.
+    pub uv_scale: Position2,
+    /// Color which base mesh color is multiplied by.
+    pub instance_color: Color,
+}
+
+vulkano::impl_vertex!(
+    UiInstance,
+    offset,
+    scale,
+    uv_offset,
+    uv_scale,
+    instance_color
+);
+
+impl UiInstance {
+    /// Creates new instance with given position and color which does not transform UV.
+    pub fn new(offset: Vec2, scale: Vec2, color: Srgba) -> Self {
+        Self {
+            offset: Position2(offset),
+            scale: Position2(scale),
+            uv_offset: Position2(Vec2::zero()),
+            uv_scale: Position2(Vec2::one()),
+            instance_color: Color(color),
+        }
+    }
+
+    /// Sets transformation of base mesh UV positions of this instance.
+    pub fn with_uv(mut self, uv_offset: Vec2, uv_scale: Vec2) -> Self {
+        self.uv_offset = Position2(uv_offset);
+        self.uv_scale = Position2(uv_scale);
+        self
+    }
+}
@@ -1,6 +1,10 @@
 //! API for simple game engine based on Rust and Vulkan API.
 
 pub use app::init;
+pub use graphics::{
+    instance, submit_ui, surface, ui_srgb_view, BlurBehind, UiCapture, UiSrgbViewError,
+    UiSubmitError, UiSupersampler,
+};
 
 pub mod app;
 pub mod config;
@@ -1,16 +1,19 @@
 //! Utilities for window handling of game engine.
 
 use egui::CtxRef;
+use winit::window::Window;
 
 use crate::app::DeltaTime;
 
+mod tests;
+
 /// General event of game engine window.
 pub enum Event {
     /// Called when game window was created.
     Created,
 
     /// Called when game window was resized.
-    Resized(Size),
+    Resized(PhysicalSize),
 
     /// Called when game window needs updating.
     Update(DeltaTime),
@@ -22,40 +25,154 @@ pub enum Event {
     Destroyed,
 }
 
-/// Size of game engine window.
-#[derive(Default, Copy, Clone)]
-pub struct Size {
+/// Size of game engine window (or any render target) in physical pixels.
+///
+/// Swapchain images, viewports and scissors are always measured in physical pixels.
+/// Use [`to_logical`](PhysicalSize::to_logical) to get size in logical points.
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PhysicalSize {
     pub width: u32,
     pub height: u32,
 }
 
-impl Size {
+impl PhysicalSize {
     /// Creates new size of window.
     pub const fn new(width: u32, height: u32) -> Self {
         Self { width, height }
     }
+
+    /// Converts this size into logical points with given ratio
+    /// between physical pixels and logical points.
+    pub fn to_logical(self, scale_factor: f32) -> LogicalSize {
+        LogicalSize::new(
+            self.width as f32 / scale_factor,
+            self.height as f32 / scale_factor,
+        )
+    }
+
+    /// Ratio between width and height.
+    ///
+    /// Returns `1.0` if height is zero (e.g. when window is minimized).
+    ///
+    pub fn aspect_ratio(self) -> f32 {
+        if self.height == 0 {
+            return 1.0;
+        }
+        self.width as f32 / self.height as f32
+    }
+
+    /// Returns size scaled by given factor and rounded to the nearest integers.
+    pub fn scaled(self, factor: f32) -> Self {
+        let scale = |value: u32| (value as f32 * factor).round().max(0.0) as u32;
+        Self::new(scale(self.width), scale(self.height))
+    }
+
+    /// Returns size which width and height are clamped between ones of `min` and `max`.
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        Self::new(
+            self.width.clamp(min.width, max.width),
+            self.height.clamp(min.height, max.height),
+        )
+    }
 }
 
-impl From<[u32; 2]> for Size {
+impl From<[u32; 2]> for PhysicalSize {
     fn from(array: [u32; 2]) -> Self {
         Self::new(array[0], array[1])
     }
 }
 
-impl From<Size> for [u32; 2] {
-    fn from(size: Size) -> Self {
+impl From<PhysicalSize> for [u32; 2] {
+    fn from(size: PhysicalSize) -> Self {
         [size.width, size.height]
     }
 }
 
-impl From<(u32, u32)> for Size {
+impl From<(u32, u32)> for PhysicalSize {
     fn from(tuple: (u32, u32)) -> Self {
         Self::new(tuple.0, tuple.1)
     }
 }
 
-impl From<Size> for (u32, u32) {
-    fn from(size: Size) -> Self {
+impl From<PhysicalSize> for (u32, u32) {
+    fn from(size: PhysicalSize) -> Self {
         (size.width, size.height)
     }
 }
+
+impl From<winit::dpi::PhysicalSize<u32>> for PhysicalSize {
+    fn from(size: winit::dpi::PhysicalSize<u32>) -> Self {
+        Self::new(size.width, size.height)
+    }
+}
+
+/// Size of game engine window (or any render target) in logical points,
+/// which are independent of DPI of the screen.
+///
+/// UI is laid out in logical points. Use [`to_physical`](LogicalSize::to_physical)
+/// to get size in physical pixels.
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct LogicalSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl LogicalSize {
+    /// Creates new logical size of window.
+    pub const fn new(width: f32, height: f32) -> Self {
+        Self { width, height }
+    }
+
+    /// Converts this size into physical pixels with given ratio
+    /// between physical pixels and logical points.
+    ///
+    /// Width and height are rounded to the nearest integers.
+    ///
+    pub fn to_physical(self, scale_factor: f32) -> PhysicalSize {
+        let scale = |value: f32| (value * scale_factor).round().max(0.0) as u32;
+        PhysicalSize::new(scale(self.width), scale(self.height))
+    }
+}
+
+impl From<[f32; 2]> for LogicalSize {
+    fn from(array: [f32; 2]) -> Self {
+        Self::new(array[0], array[1])
+    }
+}
+
+impl From<LogicalSize> for [f32; 2] {
+    fn from(size: LogicalSize) -> Self {
+        [size.width, size.height]
+    }
+}
+
+/// Metrics of game engine window which are needed to draw UI.
+#[derive(Copy, Clone)]
+pub struct WindowMetrics {
+    /// Inner size of the window in physical pixels.
+    pub size: PhysicalSize,
+
+    /// Ratio between physical pixels and logical points of the window.
+    pub scale_factor: f32,
+}
+
+impl WindowMetrics {
+    /// Retrieves actual metrics of given window.
+    ///
+    /// Should be retrieved again after the window was resized
+    /// or its scale factor was changed.
+    ///
+    pub fn from_window(window: &Window) -> Self {
+        Self {
+            size: window.inner_size().into(),
+            scale_factor: window.scale_factor() as f32,
+        }
+    }
+
+    /// Inner size of the window in logical points.
+    pub fn logical_size(&self) -> LogicalSize {
+        self.size.to_logical(self.scale_factor)
+    }
+}
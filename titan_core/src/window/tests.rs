@@ -0,0 +1,52 @@
+#![cfg(test)]
+
+use super::*;
+
+#[test]
+fn test_aspect_ratio() {
+    assert_eq!(PhysicalSize::new(800, 400).aspect_ratio(), 2.0);
+    assert_eq!(PhysicalSize::new(300, 600).aspect_ratio(), 0.5);
+
+    // Minimized window has zero height.
+    assert_eq!(PhysicalSize::new(800, 0).aspect_ratio(), 1.0);
+}
+
+#[test]
+fn test_scaled() {
+    assert_eq!(
+        PhysicalSize::new(800, 600).scaled(1.5),
+        PhysicalSize::new(1200, 900)
+    );
+    assert_eq!(PhysicalSize::new(3, 5).scaled(0.5), PhysicalSize::new(2, 3));
+    assert_eq!(
+        PhysicalSize::new(800, 600).scaled(-1.0),
+        PhysicalSize::default()
+    );
+}
+
+#[test]
+fn test_clamp() {
+    let min = PhysicalSize::new(100, 100);
+    let max = PhysicalSize::new(1920, 1080);
+    assert_eq!(
+        PhysicalSize::new(50, 2000).clamp(min, max),
+        PhysicalSize::new(100, 1080)
+    );
+    assert_eq!(
+        PhysicalSize::new(640, 480).clamp(min, max),
+        PhysicalSize::new(640, 480)
+    );
+}
+
+#[test]
+fn test_logical_physical_conversion() {
+    let physical = PhysicalSize::new(1920, 1080);
+    assert_eq!(physical.to_logical(1.5), LogicalSize::new(1280.0, 720.0));
+    assert_eq!(physical.to_logical(1.5).to_physical(1.5), physical);
+
+    // Fractional logical sizes are rounded to the nearest pixels.
+    assert_eq!(
+        LogicalSize::new(100.3, 50.7).to_physical(1.0),
+        PhysicalSize::new(100, 51)
+    );
+}
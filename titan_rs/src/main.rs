@@ -7,7 +7,11 @@
 
 use egui::{TopBottomPanel, Window};
 
-use titan_core::{app::DeltaTime, config::Config, window::Event};
+use titan_core::{
+    app::{DeltaTime, TextureOptions},
+    config::Config,
+    window::Event,
+};
 
 mod logger;
 
@@ -21,8 +25,7 @@ fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     log::info!("logger initialized successfully");
 
     let version = APP_VERSION_STR.parse().unwrap();
-    let enable_validation = cfg!(debug_assertions);
-    let config = Config::new(APP_NAME.to_string(), version, enable_validation);
+    let config = Config::with_default_validation(APP_NAME.to_string(), version);
 
     let mut delta_time = DeltaTime::ZERO;
     let mut duration = DeltaTime::ZERO;
@@ -36,7 +39,7 @@ fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
         .with_guessed_format()?
         .decode()?
         .to_rgba8();
-    let texture_id = application.register_ui_image(&image)?;
+    let texture_id = application.register_ui_image(&image, TextureOptions::default())?;
 
     application.run(move |event| match event {
         Event::Created => {